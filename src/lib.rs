@@ -35,7 +35,15 @@
 
 #[macro_use]
 extern crate bitflags;
+#[macro_use]
+extern crate log;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
+use std::borrow::Cow;
 use std::fmt;
 use std::os;
 
@@ -86,16 +94,27 @@ pub trait ResourceHandle: fmt::Debug + Sized {
     fn initialize(&self, ctx: &mut Context, desc: Self::Description) -> Option<Self>;
 
     /// Discard a resource object.
+    ///
+    /// Discarding a stale or already discarded handle, or one whose
+    /// resource was destroyed by [`Context::shutdown()`], is a no-op.
+    ///
+    /// [`Context::shutdown()`]: struct.Context.html#method.shutdown
     fn discard(self, ctx: &mut Context);
 
     /// Get the underlying resource ID.
     fn id(&self) -> u32;
+
+    /// Query the state of the resource.
+    ///
+    /// Stale and discarded handles report `ResourceState::Initial`.
+    fn state(&self, ctx: &Context) -> ResourceState;
 }
 
 /// A buffer resource handle.
 ///
 /// Buffers contain vertex and index data.
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Buffer {
     /// The ID of the underlying buffer resource.
     id: u32,
@@ -120,23 +139,49 @@ impl ResourceHandle for Buffer {
 
     /// Initialize an allocated `Buffer` resource handle.
     fn initialize(&self, ctx: &mut Context, desc: Self::Description) -> Option<Self> {
+        if !validate_buffer_content(&desc) {
+            ctx.buffer_pool.release(*self);
+            return None;
+        }
+        match ctx.backend.create_buffer(&desc) {
+            Some(mut buf) => {
+                buf.slot = pool::Slot {
+                    id: self.id,
+                    state: ResourceState::Valid,
+                };
+                ctx.buffer_pool.insert(*self, buf);
+            }
+            None => {
+                ctx.buffer_pool.release(*self);
+                return None;
+            }
+        }
+        ctx.trace(TraceEvent::MakeBuffer(*self));
         Some(*self)
     }
 
     /// Discard a `Buffer` resource object.
     fn discard(self, ctx: &mut Context) {
-        ctx.buffer_pool.discard(self, &mut ctx.backend);
+        ctx.trace(TraceEvent::DestroyBuffer(self));
+        if let Some(buf) = ctx.buffer_pool.discard(self) {
+            ctx.backend.destroy_buffer(buf);
+        }
     }
 
     fn id(&self) -> u32 {
         self.id
     }
+
+    fn state(&self, ctx: &Context) -> ResourceState {
+        ctx.buffer_pool.state(self, |buf| buf.slot.state)
+    }
 }
 
 /// An image resource handle.
 ///
 /// Images represent textures and render targets.
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Image {
     /// The ID of the underlying image resource.
     id: u32,
@@ -161,21 +206,164 @@ impl ResourceHandle for Image {
 
     /// Initialize an allocated `Image` resource handle.
     fn initialize(&self, ctx: &mut Context, desc: Self::Description) -> Option<Self> {
+        if desc.render_target
+            && !desc.pixel_format.is_valid_rendertarget_color_format()
+            && !desc.pixel_format.is_valid_rendertarget_depth_format()
+        {
+            warn!(
+                "make_image: pixel format {:?} can't be used for a render target",
+                desc.pixel_format
+            );
+            ctx.image_pool.release(*self);
+            return None;
+        }
+        if !ctx.validate_image_limits(&desc) || !validate_cube_image(&desc) {
+            ctx.image_pool.release(*self);
+            return None;
+        }
+        if !validate_image_content(&desc) {
+            ctx.image_pool.release(*self);
+            return None;
+        }
+        if desc.min_lod > desc.max_lod {
+            warn!(
+                "make_image: min_lod {} exceeds max_lod {}",
+                desc.min_lod, desc.max_lod
+            );
+            ctx.image_pool.release(*self);
+            return None;
+        }
+        match ctx.backend.create_image(&desc) {
+            Some(mut img) => {
+                img.slot = pool::Slot {
+                    id: self.id,
+                    state: ResourceState::Valid,
+                };
+                ctx.image_pool.insert(*self, img);
+            }
+            None => {
+                ctx.image_pool.release(*self);
+                return None;
+            }
+        }
+        ctx.trace(TraceEvent::MakeImage(*self));
         Some(*self)
     }
 
     /// Discard an `Image` resource object.
     fn discard(self, ctx: &mut Context) {
-        ctx.image_pool.discard(self, &mut ctx.backend);
+        ctx.trace(TraceEvent::DestroyImage(self));
+        if let Some(img) = ctx.image_pool.discard(self) {
+            ctx.backend.destroy_image(img);
+        }
     }
 
     fn id(&self) -> u32 {
         self.id
     }
+
+    fn state(&self, ctx: &Context) -> ResourceState {
+        ctx.image_pool.state(self, |img| img.slot.state)
+    }
+}
+
+/// Check that an immutable buffer is created with content, unless it wraps
+/// externally created backend buffers.
+fn validate_buffer_content(desc: &BufferDesc) -> bool {
+    #[cfg(feature = "gl")]
+    let external = desc.gl_buffers[0] != 0;
+    #[cfg(not(feature = "gl"))]
+    let external = false;
+    if desc.usage == Usage::Immutable && desc.content.is_empty() && !external {
+        warn!("make_buffer: immutable buffer must be created with content");
+        return false;
+    }
+    true
+}
+
+/// Check that an immutable image is created with content, unless it is a
+/// render target or wraps externally created backend textures.
+fn validate_image_content(desc: &ImageDesc) -> bool {
+    #[cfg(feature = "gl")]
+    let external = desc.gl_textures[0] != 0;
+    #[cfg(not(feature = "gl"))]
+    let external = false;
+    if desc.usage == Usage::Immutable
+        && !desc.render_target
+        && !external
+        && desc.content.subimage[0][0].content.is_empty()
+    {
+        warn!("make_image: immutable image must be created with content");
+        return false;
+    }
+    true
+}
+
+/// Check that a cube image is square and, if immutable, has content for
+/// all six faces of every mipmap level.
+fn validate_cube_image(desc: &ImageDesc) -> bool {
+    if desc.image_type != ImageType::Cube {
+        return true;
+    }
+    if desc.width != desc.height {
+        warn!(
+            "make_image: cube image must be square, got {}x{}",
+            desc.width, desc.height
+        );
+        return false;
+    }
+    if desc.usage == Usage::Immutable && !desc.render_target {
+        for mip in 0..desc.num_mipmaps.max(1) {
+            for face in 0..CUBEFACE_NUM {
+                if desc.content.subimage[mip][face].content.is_empty() {
+                    warn!(
+                        "make_image: cube image is missing face {} of mipmap {}",
+                        face, mip
+                    );
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Whether a vertex attribute is used, i.e. has a name or semantic name.
+fn is_used_vertex_attr(attr: &VertexAttrDesc) -> bool {
+    !attr.name.is_empty() || !attr.sem_name.is_empty()
+}
+
+/// Fill in the vertex attribute offsets and layout strides left at 0.
+///
+/// If all attributes of a layout have offset 0, they are tightly packed in
+/// order. A stride of 0 is resolved to the end of the last attribute.
+fn resolve_vertex_layouts(layouts: &mut [VertexLayoutDesc]) {
+    for layout in layouts {
+        let auto_offset = layout
+            .attrs
+            .iter()
+            .filter(|attr| is_used_vertex_attr(attr))
+            .all(|attr| attr.offset == 0);
+        let mut end = 0;
+        for attr in layout
+            .attrs
+            .iter_mut()
+            .filter(|attr| is_used_vertex_attr(attr))
+        {
+            if auto_offset {
+                attr.offset = end;
+            }
+            end = end.max(attr.offset + attr.format.bytesize() as u32);
+        }
+        if layout.stride == 0 {
+            layout.stride = end;
+        }
+    }
 }
 
 /// A shader resource handle.
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Shader {
     /// The ID of the underlying shader resource.
     id: u32,
@@ -198,25 +386,85 @@ impl ResourceHandle for Shader {
         ctx.shader_pool.alloc()
     }
 
+    /// Create a shader, returning its slot to the pool if this fails.
+    fn make(ctx: &mut Context, desc: Self::Description) -> Option<Self> {
+        let shd = Self::alloc(ctx)?;
+        let made = shd.initialize(ctx, desc);
+        if made.is_none() {
+            ctx.shader_pool.release(shd);
+        }
+        made
+    }
+
     /// Initialize an allocated `Shader` resource handle.
+    ///
+    /// This compiles and links the shader, so it can be deferred until the
+    /// shader is needed. Until then, pipelines can't be created with the
+    /// shader. If initialization fails, the shader remains allocated in the
+    /// `Failed` state until it is discarded.
     fn initialize(&self, ctx: &mut Context, desc: Self::Description) -> Option<Self> {
+        if !ctx.shader_pool.contains(self) || ctx.shader_pool.lookup(self).is_some() {
+            warn!("make_shader: {:?} is not an uninitialized shader", self);
+            return None;
+        }
+        for &(name, stage) in &[("vertex", &desc.vs), ("fragment", &desc.fs)] {
+            if stage.source.is_empty() && stage.byte_code.is_none() {
+                warn!(
+                    "make_shader: {} shader has neither source nor byte code",
+                    name
+                );
+                return self.mark_failed(ctx);
+            }
+        }
+        match ctx.backend.create_shader(&desc) {
+            Some(mut shd) => {
+                shd.slot = pool::Slot {
+                    id: self.id,
+                    state: ResourceState::Valid,
+                };
+                ctx.shader_pool.insert(*self, shd);
+            }
+            None => return self.mark_failed(ctx),
+        }
+        ctx.trace(TraceEvent::MakeShader(*self));
         Some(*self)
     }
 
     /// Discard a `Shader` resource object.
     fn discard(self, ctx: &mut Context) {
-        ctx.shader_pool.discard(self, &mut ctx.backend);
+        ctx.trace(TraceEvent::DestroyShader(self));
+        if let Some(shd) = ctx.shader_pool.discard(self) {
+            ctx.backend.destroy_shader(shd);
+        }
     }
 
     fn id(&self) -> u32 {
         self.id
     }
+
+    fn state(&self, ctx: &Context) -> ResourceState {
+        ctx.shader_pool.state(self, |shd| shd.slot.state)
+    }
+}
+
+impl Shader {
+    /// Keep a shader whose initialization failed in the `Failed` state.
+    fn mark_failed(&self, ctx: &mut Context) -> Option<Self> {
+        let mut shd = backend::ShaderResource::default();
+        shd.slot = pool::Slot {
+            id: self.id,
+            state: ResourceState::Failed,
+        };
+        ctx.shader_pool.insert(*self, shd);
+        None
+    }
 }
 
 /// A pipeline resource handle.
 ///
 /// Pipelines handle vertex layouts, shader, and render states.
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Pipeline {
     /// The ID of the underlying pipeline resource.
     id: u32,
@@ -239,26 +487,112 @@ impl ResourceHandle for Pipeline {
         ctx.pipeline_pool.alloc()
     }
 
+    /// Create a pipeline, returning its slot to the pool if this fails.
+    fn make(ctx: &mut Context, desc: Self::Description) -> Option<Self> {
+        let pip = Self::alloc(ctx)?;
+        let made = pip.initialize(ctx, desc);
+        if made.is_none() {
+            ctx.pipeline_pool.release(pip);
+        }
+        made
+    }
+
     /// Initialize an allocated `Pipeline` resource handle.
-    fn initialize(&self, ctx: &mut Context, desc: Self::Description) -> Option<Self> {
+    ///
+    /// Until the pipeline is initialized, draw states that use it are
+    /// silently skipped. If initialization fails, the pipeline remains
+    /// allocated in the `Failed` state until it is discarded.
+    fn initialize(&self, ctx: &mut Context, mut desc: Self::Description) -> Option<Self> {
+        if !ctx.pipeline_pool.contains(self) || ctx.pipeline_pool.lookup(self).is_some() {
+            warn!("make_pipeline: {:?} is not an uninitialized pipeline", self);
+            return None;
+        }
+        resolve_vertex_layouts(&mut desc.vertex_layouts);
+        if !ctx.validate_pipeline_limits(&desc) {
+            return self.mark_failed(ctx);
+        }
+        let is_strip = matches!(
+            desc.primitive_type,
+            PrimitiveType::LineStrip | PrimitiveType::TriangleStrip
+        );
+        if desc.primitive_restart && !is_strip {
+            warn!(
+                "make_pipeline: primitive restart requires a strip primitive type, not {:?}",
+                desc.primitive_type
+            );
+            return self.mark_failed(ctx);
+        }
+        // A pipeline on a shader that failed to compile would never render.
+        let shader = match desc.shader.state(ctx) {
+            ResourceState::Valid => ctx.shader_pool.lookup(&desc.shader).cloned(),
+            ResourceState::Initial => {
+                warn!("make_pipeline: shader {:?} doesn't exist", desc.shader);
+                None
+            }
+            ResourceState::Alloc => {
+                warn!("make_pipeline: shader {:?} is not initialized", desc.shader);
+                None
+            }
+            ResourceState::Failed => {
+                warn!(
+                    "make_pipeline: shader {:?} failed to initialize",
+                    desc.shader
+                );
+                None
+            }
+        };
+        let shader = match shader {
+            Some(shader) => shader,
+            None => return self.mark_failed(ctx),
+        };
+        match ctx.backend.create_pipeline(desc, shader) {
+            Some(mut pip) => {
+                pip.slot = pool::Slot {
+                    id: self.id,
+                    state: ResourceState::Valid,
+                };
+                ctx.pipeline_pool.insert(*self, pip);
+            }
+            None => return self.mark_failed(ctx),
+        }
+        ctx.trace(TraceEvent::MakePipeline(*self));
         Some(*self)
     }
 
     /// Discard a `Pipeline` resource object.
     fn discard(self, ctx: &mut Context) {
-        ctx.pipeline_pool.discard(self, &mut ctx.backend);
+        ctx.trace(TraceEvent::DestroyPipeline(self));
+        ctx.pipeline_pool.discard(self);
     }
 
     fn id(&self) -> u32 {
         self.id
     }
+
+    fn state(&self, ctx: &Context) -> ResourceState {
+        ctx.pipeline_pool.state(self, |pip| pip.slot.state)
+    }
+}
+
+impl Pipeline {
+    /// Keep a pipeline whose initialization failed in the `Failed` state.
+    fn mark_failed(&self, ctx: &mut Context) -> Option<Self> {
+        let mut pip = backend::PipelineResource::default();
+        pip.slot = pool::Slot {
+            id: self.id,
+            state: ResourceState::Failed,
+        };
+        ctx.pipeline_pool.insert(*self, pip);
+        None
+    }
 }
 
 /// A pass resource handle.
 ///
 /// Passes manage render passes and actions on render targets,
 /// like clear or MSAA resolve operations.
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Pass {
     /// The ID of the underlying pass resource.
     id: u32,
@@ -283,19 +617,86 @@ impl ResourceHandle for Pass {
 
     /// Initialize an allocated `Pass` resource handle.
     fn initialize(&self, ctx: &mut Context, desc: Self::Description) -> Option<Self> {
+        if let Err(err) = ctx.validate_pass_desc(&desc) {
+            warn!("make_pass: {}", err);
+            ctx.pass_pool.release(*self);
+            return None;
+        }
+        let color_images: Vec<_> = desc.color_attachments[..desc.num_color_attachments()]
+            .iter()
+            .filter_map(|att| ctx.image_pool.lookup(&att.image).cloned())
+            .collect();
+        let ds_image = desc
+            .depth_stencil_attachment
+            .as_ref()
+            .and_then(|att| ctx.image_pool.lookup(&att.image).cloned());
+        match ctx.backend.create_pass(&desc, color_images, ds_image) {
+            Some(mut pass) => {
+                pass.slot = pool::Slot {
+                    id: self.id,
+                    state: ResourceState::Valid,
+                };
+                ctx.pass_pool.insert(*self, pass);
+            }
+            None => {
+                ctx.pass_pool.release(*self);
+                return None;
+            }
+        }
+        ctx.trace(TraceEvent::MakePass(*self));
         Some(*self)
     }
 
     /// Discard a `Pass` resource object.
     fn discard(self, ctx: &mut Context) {
-        ctx.pass_pool.discard(self, &mut ctx.backend);
+        ctx.trace(TraceEvent::DestroyPass(self));
+        if let Some(pass) = ctx.pass_pool.discard(self) {
+            ctx.backend.destroy_pass(pass);
+        }
     }
 
     fn id(&self) -> u32 {
         self.id
     }
+
+    fn state(&self, ctx: &Context) -> ResourceState {
+        ctx.pass_pool.state(self, |pass| pass.slot.state)
+    }
+}
+
+/// Implement the raw ID accessors shared by all resource handles.
+macro_rules! impl_handle_ids {
+    ($($handle:ident),*) => {
+        $(
+            impl $handle {
+                /// Whether this handle refers to a resource, rather than being
+                /// the default, invalid handle.
+                ///
+                /// This doesn't check whether the resource still exists.
+                pub fn is_valid(self) -> bool {
+                    self.id != INVALID_ID
+                }
+
+                /// Get the raw ID of this handle, such as to pass it across
+                /// an FFI boundary.
+                pub fn raw(self) -> u32 {
+                    self.id
+                }
+
+                /// Reconstruct a handle from a raw ID previously returned by
+                /// [`raw()`].
+                ///
+                /// [`raw()`]: #method.raw
+                pub fn from_raw(id: u32) -> Self {
+                    $handle { id }
+                }
+            }
+        )*
+    };
 }
 
+impl_handle_ids!(Buffer, Image, Shader, Pipeline, Pass);
+
 #[allow(dead_code, missing_docs)]
 const INVALID_ID: u32 = 0;
 #[allow(missing_docs)]
@@ -339,6 +740,7 @@ pub const CUBEFACE_NUM: usize = 6;
 /// [`query_feature()`]: fn.query_feature.html
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Feature {
     Instancing,
     TextureCompressionDXT,
@@ -356,6 +758,44 @@ pub enum Feature {
     ImageTypeArray,
 }
 
+/// Every `Feature`, in declaration order.
+const ALL_FEATURES: [Feature; 14] = [
+    Feature::Instancing,
+    Feature::TextureCompressionDXT,
+    Feature::TextureCompressionPVRTC,
+    Feature::TextureCompressionATC,
+    Feature::TextureCompressionETC2,
+    Feature::TextureFloat,
+    Feature::TextureHalfFloat,
+    Feature::OriginBottomLeft,
+    Feature::OriginTopLeft,
+    Feature::MSAARenderTargets,
+    Feature::PackedVertexFormat_10_2,
+    Feature::MultipleRenderTarget,
+    Feature::ImageType3D,
+    Feature::ImageTypeArray,
+];
+
+/// Whether a [`Feature`] is supported and, if not, why.
+///
+/// This is returned by [`query_feature_detail()`].
+///
+/// [`Feature`]: enum.Feature.html
+/// [`query_feature_detail()`]: struct.Context.html#method.query_feature_detail
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FeatureSupport {
+    /// The feature is supported.
+    Supported,
+    /// The rendering backend doesn't support the feature.
+    UnsupportedByBackend,
+    /// The feature requires the named GL extension, which the driver
+    /// doesn't provide.
+    MissingExtension(&'static str),
+    /// The feature isn't available because the GL backend runs in GLES2
+    /// fallback mode.
+    DisabledByGles2Fallback,
+}
+
 /// The current state of a resource in its resource pool.
 ///
 /// Resources start in the INITIAL state, which means the
@@ -386,6 +826,155 @@ impl Default for ResourceState {
     }
 }
 
+/// Errors that can occur when operating on an existing resource.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ResourceError {
+    /// The handle does not refer to a live resource.
+    InvalidHandle,
+    /// The resource was created with `Usage::Immutable` and its content
+    /// is not accessible from the CPU.
+    Immutable,
+    /// The requested range lies outside of the resource.
+    OutOfBounds,
+    /// The image has only a single mipmap level or is not sampled with
+    /// a mipmap filter.
+    NotMipmapped,
+    /// The operation is not supported by the rendering backend.
+    Unsupported,
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResourceError::InvalidHandle => write!(f, "invalid resource handle"),
+            ResourceError::Immutable => write!(f, "resource is immutable"),
+            ResourceError::OutOfBounds => write!(f, "range is out of bounds"),
+            ResourceError::NotMipmapped => write!(f, "image is not mipmapped"),
+            ResourceError::Unsupported => write!(f, "operation is not supported"),
+        }
+    }
+}
+
+impl std::error::Error for ResourceError {}
+
+/// An attachment of a [`PassDesc`], as referenced by a
+/// [`PassValidationError`].
+///
+/// [`PassDesc`]: struct.PassDesc.html
+/// [`PassValidationError`]: enum.PassValidationError.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PassAttachment {
+    /// The color attachment at the given index.
+    Color(usize),
+    /// The depth-stencil attachment.
+    DepthStencil,
+}
+
+impl fmt::Display for PassAttachment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PassAttachment::Color(index) => write!(f, "color attachment {}", index),
+            PassAttachment::DepthStencil => write!(f, "depth-stencil attachment"),
+        }
+    }
+}
+
+/// The reason why a [`PassDesc`] is invalid.
+///
+/// This is returned by [`validate_pass_desc()`].
+///
+/// [`PassDesc`]: struct.PassDesc.html
+/// [`validate_pass_desc()`]: struct.Context.html#method.validate_pass_desc
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PassValidationError {
+    /// The pass has neither color attachments nor a depth-stencil
+    /// attachment.
+    NoColorAttachments,
+    /// The image of the attachment does not refer to a live image.
+    InvalidImage(PassAttachment),
+    /// The image of the attachment was not created as a render target.
+    NotRenderTarget(PassAttachment),
+    /// The image of the attachment differs in size from the first
+    /// color attachment.
+    SizeMismatch(PassAttachment),
+    /// The image of the attachment differs in sample count from the
+    /// first color attachment.
+    SampleCountMismatch(PassAttachment),
+    /// The image of the color attachment differs in pixel format from
+    /// the first color attachment.
+    ColorFormatMismatch(PassAttachment),
+    /// The image of the attachment is multisampled, but the backend doesn't
+    /// support `Feature::MSAARenderTargets`.
+    MSAANotSupported(PassAttachment),
+    /// The color attachment isn't resolved, but its image isn't
+    /// multisampled either, so there is no buffer to store the rendering
+    /// results in.
+    NoStoreTarget(PassAttachment),
+    /// The mip level of the attachment doesn't exist in its image.
+    InvalidMipLevel(PassAttachment),
+    /// The face, layer or slice index of the attachment is out of range for
+    /// its image.
+    InvalidIndex(PassAttachment),
+    /// The pass has more than one color attachment, but the backend doesn't
+    /// support `Feature::MultipleRenderTarget`.
+    MRTNotSupported,
+    /// The color attachment follows an unused color attachment.
+    AttachmentGap(PassAttachment),
+}
+
+impl fmt::Display for PassValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PassValidationError::NoColorAttachments => write!(f, "pass has no attachments"),
+            PassValidationError::InvalidImage(att) => write!(f, "{} has an invalid image", att),
+            PassValidationError::NotRenderTarget(att) => {
+                write!(f, "{} image is not a render target", att)
+            }
+            PassValidationError::SizeMismatch(att) => {
+                write!(f, "{} image size differs from the first attachment", att)
+            }
+            PassValidationError::SampleCountMismatch(att) => {
+                write!(
+                    f,
+                    "{} image sample count differs from the first attachment",
+                    att
+                )
+            }
+            PassValidationError::ColorFormatMismatch(att) => {
+                write!(
+                    f,
+                    "{} image pixel format differs from the first attachment",
+                    att
+                )
+            }
+            PassValidationError::MSAANotSupported(att) => {
+                write!(
+                    f,
+                    "{} image is multisampled, but MSAA is not supported",
+                    att
+                )
+            }
+            PassValidationError::NoStoreTarget(att) => {
+                write!(f, "{} is neither multisampled nor resolved", att)
+            }
+            PassValidationError::InvalidMipLevel(att) => {
+                write!(f, "{} mip level is out of range", att)
+            }
+            PassValidationError::InvalidIndex(att) => {
+                write!(f, "{} face, layer or slice index is out of range", att)
+            }
+            PassValidationError::MRTNotSupported => {
+                write!(f, "multiple render targets are not supported")
+            }
+            PassValidationError::AttachmentGap(att) => {
+                write!(f, "{} follows an unused color attachment", att)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PassValidationError {}
+
 /// A resource usage hint describing the update strategy of
 /// buffers and images. This is used in the [`BufferDesc`]
 /// and [`ImageDesc`] `usage` members when creating buffers
@@ -410,6 +999,7 @@ impl Default for ResourceState {
 /// [`update_buffer()`]: fn.update_buffer.html
 /// [`update_image()`]: fn.update_image.html
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Usage {
     /// The resource will never be updated with new data, instead, the
     /// data content of the resource must be provided on creation.
@@ -436,6 +1026,7 @@ impl Default for Usage {
 ///
 /// [`BufferDesc`]: struct.BufferDesc.html
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BufferType {
     /// Vertex data.
     VertexBuffer,
@@ -457,6 +1048,7 @@ impl Default for BufferType {
 ///
 /// [`PipelineDesc`]: struct.PipelineDesc.html
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum IndexType {
     /// Index data is 16 bit.
     UInt16,
@@ -464,6 +1056,16 @@ pub enum IndexType {
     UInt32,
 }
 
+impl IndexType {
+    /// Size in bytes of an index.
+    pub fn bytesize(self) -> usize {
+        match self {
+            IndexType::UInt16 => 2,
+            IndexType::UInt32 => 4,
+        }
+    }
+}
+
 /// Indicates the basic image type (2D texture, cube map, 3D texture, or
 /// array of 2D textures).
 ///
@@ -476,6 +1078,7 @@ pub enum IndexType {
 ///
 /// [`ImageDesc`]: struct.ImageDesc.html
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ImageType {
     /// A 2D texture.
     Texture2D,
@@ -503,6 +1106,7 @@ impl Default for ImageType {
 /// * `MAX_SHADERSTAGE_IMAGES` slots for images used as textures
 ///   by the shader function.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ShaderStage {
     /// Vertex shader stage.
     VS,
@@ -521,9 +1125,11 @@ pub enum ShaderStage {
 #[allow(missing_docs)]
 #[allow(non_camel_case_types)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PixelFormat {
     None,
     RGBA8,
+    BGRA8,
     RGB8,
     RGBA4,
     R5G6B5,
@@ -531,9 +1137,16 @@ pub enum PixelFormat {
     R10G10B10A2,
     RGBA32F,
     RGBA16F,
+    /// Packed unsigned float format for HDR render targets.
+    R11G11B10F,
+    /// Packed unsigned float format with a shared exponent. This can't be
+    /// rendered to.
+    RGB9E5,
     R32F,
     R16F,
     L8,
+    R8,
+    RG8,
     DXT1,
     DXT3,
     DXT5,
@@ -556,37 +1169,37 @@ impl Default for PixelFormat {
 impl PixelFormat {
     /// Return `true` if pixel format is a compressed format.
     pub fn is_compressed_pixel_format(self) -> bool {
-        match self {
+        matches!(
+            self,
             PixelFormat::DXT1
-            | PixelFormat::DXT3
-            | PixelFormat::DXT5
-            | PixelFormat::PVRTC2_RGB
-            | PixelFormat::PVRTC4_RGB
-            | PixelFormat::PVRTC2_RGBA
-            | PixelFormat::PVRTC4_RGBA
-            | PixelFormat::ETC2_RGB8
-            | PixelFormat::ETC2_SRGB8 => true,
-            _ => false,
-        }
+                | PixelFormat::DXT3
+                | PixelFormat::DXT5
+                | PixelFormat::PVRTC2_RGB
+                | PixelFormat::PVRTC4_RGB
+                | PixelFormat::PVRTC2_RGBA
+                | PixelFormat::PVRTC4_RGBA
+                | PixelFormat::ETC2_RGB8
+                | PixelFormat::ETC2_SRGB8
+        )
     }
 
     /// Return `true` if pixel format is a valid render target color format.
     pub fn is_valid_rendertarget_color_format(self) -> bool {
-        match self {
+        matches!(
+            self,
             PixelFormat::RGBA8
-            | PixelFormat::R10G10B10A2
-            | PixelFormat::RGBA32F
-            | PixelFormat::RGBA16F => true,
-            _ => false,
-        }
+                | PixelFormat::R10G10B10A2
+                | PixelFormat::RGBA32F
+                | PixelFormat::RGBA16F
+                | PixelFormat::R11G11B10F
+                | PixelFormat::R8
+                | PixelFormat::RG8
+        )
     }
 
     /// Return `true` if pixel format is a valid render target color format.
     pub fn is_valid_rendertarget_depth_format(self) -> bool {
-        match self {
-            PixelFormat::Depth | PixelFormat::DepthStencil => true,
-            _ => false,
-        }
+        matches!(self, PixelFormat::Depth | PixelFormat::DepthStencil)
     }
 
     /// Return `true` if pixel format is a depth-stencil format.
@@ -595,18 +1208,58 @@ impl PixelFormat {
     }
 
     /// Return the bytes per pixel for a pixel format.
+    ///
+    /// Panics for compressed, depth and `None` formats; use
+    /// [`checked_bytesize()`](#method.checked_bytesize) when the format
+    /// isn't known to be an uncompressed color format.
     pub fn bytesize(self) -> usize {
+        match self.checked_bytesize() {
+            Some(size) => size,
+            None => unreachable!(),
+        }
+    }
+
+    /// Return the bytes per pixel for a pixel format, or `None` if the
+    /// format has no per-pixel size (compressed, depth and `None` formats).
+    pub fn checked_bytesize(self) -> Option<usize> {
         match self {
-            PixelFormat::RGBA32F => 16,
-            PixelFormat::RGBA16F => 8,
-            PixelFormat::RGBA8 | PixelFormat::R10G10B10A2 | PixelFormat::R32F => 4,
-            PixelFormat::RGB8 => 3,
+            PixelFormat::RGBA32F => Some(16),
+            PixelFormat::RGBA16F => Some(8),
+            PixelFormat::RGBA8
+            | PixelFormat::BGRA8
+            | PixelFormat::R10G10B10A2
+            | PixelFormat::R11G11B10F
+            | PixelFormat::RGB9E5
+            | PixelFormat::R32F => Some(4),
+            PixelFormat::RGB8 => Some(3),
             PixelFormat::R5G5B5A1
             | PixelFormat::R5G6B5
             | PixelFormat::RGBA4
-            | PixelFormat::R16F => 2,
-            PixelFormat::L8 => 1,
-            _ => unreachable!(),
+            | PixelFormat::R16F
+            | PixelFormat::RG8 => Some(2),
+            PixelFormat::L8 | PixelFormat::R8 => Some(1),
+            _ => None,
+        }
+    }
+
+    /// Return the size of a single component of the pixel format in bytes,
+    /// or `None` for depth formats, which can't be filled with content.
+    /// Packed formats such as `R5G6B5` are made of a single component, and
+    /// compressed formats of bytes.
+    fn component_size(self) -> Option<usize> {
+        match self {
+            PixelFormat::RGBA32F
+            | PixelFormat::R32F
+            | PixelFormat::R10G10B10A2
+            | PixelFormat::R11G11B10F
+            | PixelFormat::RGB9E5 => Some(4),
+            PixelFormat::RGBA16F
+            | PixelFormat::R16F
+            | PixelFormat::R5G5B5A1
+            | PixelFormat::R5G6B5
+            | PixelFormat::RGBA4 => Some(2),
+            PixelFormat::None | PixelFormat::Depth | PixelFormat::DepthStencil => None,
+            _ => Some(1),
         }
     }
 
@@ -674,6 +1327,7 @@ impl PixelFormat {
 /// [`PipelineDesc`]: struct.PipelineDesc.html
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PrimitiveType {
     Points,
     Lines,
@@ -698,6 +1352,7 @@ impl Default for PrimitiveType {
 /// [`ImageDesc`]: struct.ImageDesc.html
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Filter {
     Nearest,
     Linear,
@@ -713,6 +1368,13 @@ impl Default for Filter {
     }
 }
 
+impl Filter {
+    /// Return `true` if this filter samples from mipmap levels.
+    pub fn is_mipmap_filter(self) -> bool {
+        !matches!(self, Filter::Nearest | Filter::Linear)
+    }
+}
+
 /// The texture coordinates wrapping mode when sampling a texture
 /// image.
 ///
@@ -724,6 +1386,7 @@ impl Default for Filter {
 /// [`ImageDesc`]: struct.ImageDesc.html
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Wrap {
     Repeat,
     ClampToEdge,
@@ -736,12 +1399,42 @@ impl Default for Wrap {
     }
 }
 
+/// The source of a color component when sampling an image.
+///
+/// This is used in the [`ImageDesc`] `swizzle` member when creating an
+/// image, such as to sample a single-channel `R8` image as grayscale with
+/// `[Red, Red, Red, One]`.
+///
+/// [`ImageDesc`]: struct.ImageDesc.html
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TextureSwizzle {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    Zero,
+    One,
+}
+
+impl TextureSwizzle {
+    /// The swizzle that samples every component from itself.
+    pub const IDENTITY: [TextureSwizzle; 4] = [
+        TextureSwizzle::Red,
+        TextureSwizzle::Green,
+        TextureSwizzle::Blue,
+        TextureSwizzle::Alpha,
+    ];
+}
+
 /// The data type of a vertex component.
 ///
 /// This is used to describe the layout of vertex data when creating
 /// a pipeline object.
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum VertexFormat {
     Float,
     Float2,
@@ -789,6 +1482,7 @@ impl VertexFormat {
 /// pipeline objects.
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum VertexStep {
     PerVertex,
     PerInstance,
@@ -806,6 +1500,7 @@ impl Default for VertexStep {
 /// when creating a shader object.
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UniformType {
     Float,
     Float2,
@@ -843,6 +1538,7 @@ impl UniformType {
 /// [`PipelineDesc`]: struct.PipelineDesc.html
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CullMode {
     None,
     Front,
@@ -855,6 +1551,33 @@ impl Default for CullMode {
     }
 }
 
+/// How polygons are rasterized.
+///
+/// This is used in the [`PipelineDesc`] `rasterizer`'s
+/// `polygon_mode` member when creating a pipeline object.
+/// Backends that can't rasterize polygons as lines or points,
+/// such as GLES, always fill them.
+///
+/// The default polygon mode is `PolygonMode::Fill`.
+///
+/// [`PipelineDesc`]: struct.PipelineDesc.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PolygonMode {
+    /// Fill the interior of polygons.
+    Fill,
+    /// Draw the edges of polygons as lines, for wireframe rendering.
+    Line,
+    /// Draw the vertices of polygons as points.
+    Point,
+}
+
+impl Default for PolygonMode {
+    fn default() -> Self {
+        PolygonMode::Fill
+    }
+}
+
 /// The vertex-winding rule that determines a front-facing
 /// primitive.
 ///
@@ -865,6 +1588,7 @@ impl Default for CullMode {
 ///
 /// [`PipelineDesc`]: struct.PipelineDesc.html
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FaceWinding {
     /// Counter-clockwise.
     CCW,
@@ -886,6 +1610,7 @@ impl Default for FaceWinding {
 /// is `CompareFunc::Always`.
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CompareFunc {
     Never,
     Less,
@@ -911,6 +1636,7 @@ impl Default for CompareFunc {
 /// The default value is `StencilOp::Keep`.
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum StencilOp {
     Keep,
     Zero,
@@ -933,6 +1659,7 @@ impl Default for StencilOp {
 /// This is used when creating a pipeline object.
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BlendFactor {
     Zero,
     One,
@@ -959,6 +1686,7 @@ pub enum BlendFactor {
 /// The default value is `BlendOp::Add`.
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BlendOp {
     Add,
     Subtract,
@@ -984,6 +1712,7 @@ bitflags! {
     /// [`PipelineDesc`]: struct.PipelineDesc.html
     #[allow(missing_docs)]
     #[repr(C)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct ColorMask: u32 {
         /// None
         const NONE = 0x10;
@@ -1015,6 +1744,7 @@ impl Default for ColorMask {
 
 /// Defines what action should be performed at the start of a render pass.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Action {
     /// Clear the render target image.
     Clear,
@@ -1025,7 +1755,7 @@ pub enum Action {
 }
 
 #[allow(missing_docs)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct ColorAttachmentAction {
     pub action: Action,
     pub val: [f32; 4usize],
@@ -1061,6 +1791,36 @@ pub struct PassAction {
     pub stencil: StencilAttachmentAction,
 }
 
+impl PassAction {
+    /// Clear all color attachments to `color`, the depth to 1.0 and the
+    /// stencil to 0.
+    pub fn clear(color: [f32; 4]) -> Self {
+        PassAction::clear_full(color, 1.0, 0)
+    }
+
+    /// Clear all color attachments to `color`, the depth to `depth` and the
+    /// stencil to `stencil`.
+    ///
+    /// Depth and stencil clears are skipped for passes without a depth or
+    /// stencil buffer, as if their action was `Action::DontCare`.
+    pub fn clear_full(color: [f32; 4], depth: f32, stencil: u8) -> Self {
+        PassAction {
+            colors: [ColorAttachmentAction {
+                action: Action::Clear,
+                val: color,
+            }; MAX_COLOR_ATTACHMENTS],
+            depth: DepthAttachmentAction {
+                action: Action::Clear,
+                val: depth,
+            },
+            stencil: StencilAttachmentAction {
+                action: Action::Clear,
+                val: stencil,
+            },
+        }
+    }
+}
+
 /// The resource binding slots of the render pipeline.
 ///
 /// This is passed to `apply_draw_state()`.
@@ -1087,51 +1847,303 @@ pub struct DrawState {
     pub fs_images: [Image; MAX_SHADERSTAGE_IMAGES],
 }
 
-/// Configuration values for the library.
+/// Information about the rendering backend of a [`Context`].
 ///
-/// It is used as a parameter to the `setup()` call.
-#[derive(Debug)]
-pub struct Config {
-    /// Defaults to 128.
-    pub buffer_pool_size: usize,
-    /// Defaults to 128.
-    pub image_pool_size: usize,
-    /// Defaults to 32.
-    pub shader_pool_size: usize,
-    /// Defaults to 64.
-    pub pipeline_pool_size: usize,
-    /// Defaults to 16.
-    pub pass_pool_size: usize,
-    #[cfg(feature = "gl")]
-    /// If this is true, the backend will operate in "GLES2 fallback mode" even
-    /// when compiled for GLES3. This is useful for falling back to traditional
-    /// WebGL if a browser doesn't support a WebGL2 context.
-    pub gl_force_gles2: bool,
-    #[cfg(feature = "gl")]
-    /// Used to look up OpenGL functions for a grafiska OpenGL context.
-    /// Commonly wraps around glutin::GlWindow::get_proc_address or a similar
-    /// method.
-    pub load_gl_symbol: opengl::GlFunctionLookup,
-    #[cfg(feature = "metal")]
-    /// A pointer to the `MTLDevice` object.
-    pub mtl_device: metal_sys::Device,
-    #[cfg(feature = "metal")]
-    /// A C callback function to obtain the `MTLRenderPassDescriptor` for the
-    /// current frame when rendering to the default framebuffer. Will be called in
-    /// `begin_default_pass()`.
-    pub mtl_renderpass_descriptor_cb: Option<unsafe extern "C" fn() -> *const os::raw::c_void>,
-    #[cfg(feature = "metal")]
-    /// A C callback function to obtain a `MTLDrawable` for the current frame when
-    /// rendering to the default framebuffer. Will be called in `end_pass()` of the
-    /// default pass.
-    pub mtl_drawable_cb: Option<unsafe extern "C" fn() -> *const os::raw::c_void>,
-    #[cfg(feature = "metal")]
-    /// The size of the global uniform buffer in bytes. This must be big enough to hold all
-    /// the uniform block updates for a single frame. The default value is 4MByte (4 * 1024 * 1024).
-    pub mtl_global_uniform_buffer_size: usize,
-    #[cfg(feature = "metal")]
-    /// The number of slots in the sampler cache. The Metal backend will share texture samples
-    /// with the same state in this cache. The default value is 64.
+/// This is returned by [`query_backend_info()`].
+///
+/// [`Context`]: struct.Context.html
+/// [`query_backend_info()`]: struct.Context.html#method.query_backend_info
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackendInfo {
+    /// The name of the backend, such as `"GLCORE33"`, `"GLES2"`, `"GLES3"`
+    /// or `"Metal"`.
+    pub name: &'static str,
+    /// The API version reported by the driver. For GL, this is the
+    /// `GL_VERSION` string.
+    pub version: String,
+    /// The renderer reported by the driver. For GL, this is the
+    /// `GL_RENDERER` string.
+    pub renderer: String,
+    /// Whether the GL backend operates in GLES2 fallback mode. This is
+    /// always `false` for other backends.
+    pub force_gles2: bool,
+}
+
+/// Counts of the rendering work submitted during a frame.
+///
+/// This is returned by [`query_frame_stats()`].
+///
+/// [`query_frame_stats()`]: struct.Context.html#method.query_frame_stats
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FrameStats {
+    /// The number of draw calls that were passed to the backend.
+    pub num_draws: u32,
+    /// The total number of instances drawn by these draw calls.
+    pub num_instances: u32,
+    /// The number of draw states that were applied.
+    pub num_apply_draw_state: u32,
+    /// The number of applied draw states that kept the current pipeline,
+    /// so its render states didn't need to be applied again.
+    pub num_redundant_state_skipped: u32,
+    /// The number of buffer updates.
+    pub num_buffer_updates: u32,
+    /// The number of image updates.
+    pub num_image_updates: u32,
+}
+
+/// Resource limits of the rendering device.
+///
+/// This is returned by [`query_limits()`]. Images and pipelines that
+/// exceed these limits fail to be created.
+///
+/// [`query_limits()`]: struct.Context.html#method.query_limits
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// The maximum width and height of a 2D or array image.
+    pub max_image_size_2d: usize,
+    /// The maximum width and height of a cube map image.
+    pub max_image_size_cube: usize,
+    /// The maximum width, height and depth of a 3D image.
+    pub max_image_size_3d: usize,
+    /// The maximum number of layers in an array image.
+    pub max_image_array_layers: usize,
+    /// The maximum number of vertex attributes of a pipeline.
+    pub max_vertex_attributes: usize,
+    /// The maximum size of a uniform block, in bytes.
+    pub max_uniform_block_size: usize,
+    /// The maximum sample count of multisampled render targets, or 1 if
+    /// they aren't supported.
+    pub max_sample_count: usize,
+}
+
+/// How a [`PixelFormat`] can be used on the rendering device.
+///
+/// This is returned by [`query_pixelformat()`].
+///
+/// [`PixelFormat`]: enum.PixelFormat.html
+/// [`query_pixelformat()`]: struct.Context.html#method.query_pixelformat
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PixelFormatInfo {
+    /// Images of this format can be sampled by shaders.
+    pub sample: bool,
+    /// Images of this format can be sampled with linear filtering.
+    pub filter: bool,
+    /// Images of this format can be used as render targets.
+    pub render: bool,
+    /// Render targets of this format support blending.
+    pub blend: bool,
+    /// Render targets of this format can be multisampled.
+    pub msaa: bool,
+}
+
+/// The properties of a [`Buffer`], as returned by [`query_buffer_info()`].
+///
+/// [`Buffer`]: struct.Buffer.html
+/// [`query_buffer_info()`]: struct.Context.html#method.query_buffer_info
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BufferInfo {
+    /// The size of the buffer in bytes.
+    pub size: usize,
+    /// Whether this is a vertex or index buffer.
+    pub buffer_type: BufferType,
+    /// The usage the buffer was created with.
+    pub usage: Usage,
+    /// The state of the buffer resource.
+    pub state: ResourceState,
+}
+
+/// The properties of an [`Image`], as returned by [`query_image_info()`].
+///
+/// [`Image`]: struct.Image.html
+/// [`query_image_info()`]: struct.Context.html#method.query_image_info
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ImageInfo {
+    /// The type of the image.
+    pub image_type: ImageType,
+    /// The width of the image in pixels.
+    pub width: usize,
+    /// The height of the image in pixels.
+    pub height: usize,
+    /// The depth of a 3D image, or the number of layers of an array image.
+    pub depth_or_layers: usize,
+    /// The pixel format of the image.
+    pub pixel_format: PixelFormat,
+    /// The number of mipmap levels.
+    pub num_mipmaps: usize,
+    /// The number of MSAA samples of a render target.
+    pub sample_count: usize,
+    /// Whether the image can be used as a render target.
+    pub render_target: bool,
+    /// The usage the image was created with.
+    pub usage: Usage,
+    /// The state of the image resource.
+    pub state: ResourceState,
+}
+
+/// A subimage that a [`Pass`] renders into.
+///
+/// [`Pass`]: struct.Pass.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AttachmentImage {
+    /// The attached image.
+    pub image: Image,
+    /// The mip level that is rendered.
+    pub mip_level: usize,
+    /// The cube map face, array layer or depth slice that is rendered.
+    pub index: usize,
+}
+
+/// The images of a [`Pass`], as returned by [`query_pass_attachments()`].
+///
+/// [`Pass`]: struct.Pass.html
+/// [`query_pass_attachments()`]: struct.Context.html#method.query_pass_attachments
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PassAttachments {
+    /// The used color attachments, in attachment order.
+    pub color_attachments: Vec<AttachmentImage>,
+    /// The depth-stencil attachment, if any.
+    pub depth_stencil_attachment: Option<AttachmentImage>,
+}
+
+/// An event reported to the [`TraceHook`] of a [`Context`].
+///
+/// [`TraceHook`]: struct.TraceHook.html
+/// [`Context`]: struct.Context.html
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TraceEvent {
+    MakeBuffer(Buffer),
+    MakeImage(Image),
+    MakeShader(Shader),
+    MakePipeline(Pipeline),
+    MakePass(Pass),
+    DestroyBuffer(Buffer),
+    DestroyImage(Image),
+    DestroyShader(Shader),
+    DestroyPipeline(Pipeline),
+    DestroyPass(Pass),
+    BeginDefaultPass {
+        width: u32,
+        height: u32,
+    },
+    BeginPass(Pass),
+    ApplyDrawState(Pipeline),
+    Draw {
+        base_element: u32,
+        num_elements: u32,
+        num_instances: u32,
+    },
+    EndPass,
+    Commit,
+}
+
+/// A callback invoked by a [`Context`] for each [`TraceEvent`].
+///
+/// This can be used to count draw calls or to layer captures and
+/// debugging tools on top of a context.
+///
+/// [`Context`]: struct.Context.html
+/// [`TraceEvent`]: enum.TraceEvent.html
+pub struct TraceHook {
+    hook: Box<dyn Fn(TraceEvent)>,
+}
+
+impl TraceHook {
+    /// Wrap a callback in a trace hook.
+    pub fn new<F: Fn(TraceEvent) + 'static>(hook: F) -> Self {
+        TraceHook {
+            hook: Box::new(hook),
+        }
+    }
+}
+
+impl fmt::Debug for TraceHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "trace hook")
+    }
+}
+
+/// Configuration values for the library.
+///
+/// It is used as a parameter to the `setup()` call.
+#[derive(Debug)]
+pub struct Config {
+    /// Defaults to 128.
+    pub buffer_pool_size: usize,
+    /// Defaults to 128.
+    pub image_pool_size: usize,
+    /// Defaults to 32.
+    pub shader_pool_size: usize,
+    /// Defaults to 64.
+    pub pipeline_pool_size: usize,
+    /// Defaults to 16.
+    pub pass_pool_size: usize,
+    /// Called for resource creation and destruction, passes, draws
+    /// and commits. Defaults to `None`.
+    pub trace_hook: Option<TraceHook>,
+    /// Check draw states against their pipeline before drawing, skipping
+    /// and logging draws that would be invalid. Defaults to `true` in
+    /// debug builds.
+    pub validation: bool,
+    /// Reuse the slots of destroyed resources immediately, so that handles
+    /// to destroyed resources stop working as soon as possible. This is
+    /// meant for finding use-after-destroy bugs. Defaults to `false`.
+    pub debug_pool_lifo: bool,
+    /// Panic when a resource is looked up through a handle that was never
+    /// created or whose resource was destroyed, instead of ignoring the
+    /// call. The panic message names the resource type and the mismatched
+    /// handle generation. The `query_*()` functions still return `None`
+    /// for invalid handles, and unused default handles never panic. This is
+    /// meant for finding use-after-destroy bugs during development. Defaults
+    /// to `false`.
+    pub panic_on_invalid_handle: bool,
+    /// Never use the default framebuffer, for example when rendering on a
+    /// server without a window. Only offscreen passes can be rendered, and
+    /// `begin_default_pass()` starts a pass that drops all rendering calls.
+    /// Defaults to `false`.
+    pub headless: bool,
+    #[cfg(feature = "gl")]
+    /// If this is true, the backend will operate in "GLES2 fallback mode" even
+    /// when compiled for GLES3. This is useful for falling back to traditional
+    /// WebGL if a browser doesn't support a WebGL2 context.
+    pub gl_force_gles2: bool,
+    #[cfg(feature = "gl")]
+    /// Sample images through GL sampler objects, which are shared between
+    /// images with the same filter, wrap, anisotropy and LOD settings. Only
+    /// supported by the `glcore33` backend outside of GLES2 fallback mode,
+    /// and ignored otherwise. Defaults to `false`.
+    pub gl_use_sampler_objects: bool,
+    #[cfg(feature = "gl")]
+    /// Filter cube map images across face edges with
+    /// `GL_TEXTURE_CUBE_MAP_SEAMLESS`. Only supported by the `glcore33`
+    /// backend outside of GLES2 fallback mode. GLES3 always samples cube
+    /// maps seamlessly, as does Metal. Defaults to `false`.
+    pub gl_seamless_cubemaps: bool,
+    #[cfg(feature = "gl")]
+    /// Used to look up OpenGL functions for a grafiska OpenGL context.
+    /// Commonly wraps around glutin::GlWindow::get_proc_address or a similar
+    /// method.
+    pub load_gl_symbol: opengl::GlFunctionLookup,
+    #[cfg(feature = "metal")]
+    /// A pointer to the `MTLDevice` object.
+    pub mtl_device: metal_sys::Device,
+    #[cfg(feature = "metal")]
+    /// A C callback function to obtain the `MTLRenderPassDescriptor` for the
+    /// current frame when rendering to the default framebuffer. Will be called in
+    /// `begin_default_pass()`.
+    pub mtl_renderpass_descriptor_cb: Option<unsafe extern "C" fn() -> *const os::raw::c_void>,
+    #[cfg(feature = "metal")]
+    /// A C callback function to obtain a `MTLDrawable` for the current frame when
+    /// rendering to the default framebuffer. Will be called in `end_pass()` of the
+    /// default pass.
+    pub mtl_drawable_cb: Option<unsafe extern "C" fn() -> *const os::raw::c_void>,
+    #[cfg(feature = "metal")]
+    /// The size of the global uniform buffer in bytes. This must be big enough to hold all
+    /// the uniform block updates for a single frame. The default value is 4MByte (4 * 1024 * 1024).
+    pub mtl_global_uniform_buffer_size: usize,
+    #[cfg(feature = "metal")]
+    /// The number of slots in the sampler cache. The Metal backend will share texture samples
+    /// with the same state in this cache. The default value is 64.
     pub mtl_sampler_cache_size: usize,
     #[cfg(feature = "d3d11")]
     /// A pointer to the `ID3D11Device` object. This must have been created before
@@ -1160,9 +2172,18 @@ impl Default for Config {
             shader_pool_size: 32,
             pipeline_pool_size: 64,
             pass_pool_size: 16,
+            trace_hook: None,
+            validation: cfg!(debug_assertions),
+            debug_pool_lifo: false,
+            panic_on_invalid_handle: false,
+            headless: false,
             #[cfg(feature = "gl")]
             gl_force_gles2: false,
             #[cfg(feature = "gl")]
+            gl_use_sampler_objects: false,
+            #[cfg(feature = "gl")]
+            gl_seamless_cubemaps: false,
+            #[cfg(feature = "gl")]
             load_gl_symbol: opengl::GlFunctionLookup::new(|sym| {
                 std::ptr::null::<os::raw::c_void> as *const os::raw::c_void
             }),
@@ -1188,6 +2209,262 @@ impl Default for Config {
     }
 }
 
+impl Config {
+    /// Start building a configuration from the default values.
+    ///
+    /// Unlike a `Config` that is filled in directly, the configuration
+    /// returned by [`ConfigBuilder::build()`] has been validated.
+    ///
+    /// [`ConfigBuilder::build()`]: struct.ConfigBuilder.html#method.build
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder {
+            config: Config::default(),
+        }
+    }
+}
+
+/// The reason why a [`ConfigBuilder`] could not build a [`Config`].
+///
+/// [`ConfigBuilder`]: struct.ConfigBuilder.html
+/// [`Config`]: struct.Config.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ConfigError {
+    /// The size of the named resource pool is 0 or too large.
+    InvalidPoolSize {
+        /// The name of the pool, such as `"buffer"`.
+        pool: &'static str,
+        /// The requested size.
+        size: usize,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::InvalidPoolSize { pool: name, size } => write!(
+                f,
+                "{} pool size {} is not within 1..{}",
+                name,
+                size,
+                pool::MAX_POOL_SIZE
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// A builder for a validated [`Config`].
+///
+/// This is created by [`Config::builder()`].
+///
+/// [`Config`]: struct.Config.html
+/// [`Config::builder()`]: struct.Config.html#method.builder
+#[derive(Debug)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    /// Set the number of buffers that can exist at the same time.
+    pub fn buffer_pool_size(mut self, size: usize) -> Self {
+        self.config.buffer_pool_size = size;
+        self
+    }
+
+    /// Set the number of images that can exist at the same time.
+    pub fn image_pool_size(mut self, size: usize) -> Self {
+        self.config.image_pool_size = size;
+        self
+    }
+
+    /// Set the number of shaders that can exist at the same time.
+    pub fn shader_pool_size(mut self, size: usize) -> Self {
+        self.config.shader_pool_size = size;
+        self
+    }
+
+    /// Set the number of pipelines that can exist at the same time.
+    pub fn pipeline_pool_size(mut self, size: usize) -> Self {
+        self.config.pipeline_pool_size = size;
+        self
+    }
+
+    /// Set the number of passes that can exist at the same time.
+    pub fn pass_pool_size(mut self, size: usize) -> Self {
+        self.config.pass_pool_size = size;
+        self
+    }
+
+    /// Set the callback for tracing context events.
+    pub fn trace_hook(mut self, trace_hook: TraceHook) -> Self {
+        self.config.trace_hook = Some(trace_hook);
+        self
+    }
+
+    /// Enable or disable draw state validation.
+    pub fn validation(mut self, validation: bool) -> Self {
+        self.config.validation = validation;
+        self
+    }
+
+    /// Reuse the slots of destroyed resources immediately.
+    pub fn debug_pool_lifo(mut self, lifo: bool) -> Self {
+        self.config.debug_pool_lifo = lifo;
+        self
+    }
+
+    /// Panic when a resource is looked up through an invalid handle.
+    pub fn panic_on_invalid_handle(mut self, panic: bool) -> Self {
+        self.config.panic_on_invalid_handle = panic;
+        self
+    }
+
+    /// Never use the default framebuffer.
+    pub fn headless(mut self, headless: bool) -> Self {
+        self.config.headless = headless;
+        self
+    }
+
+    /// Operate the GL backend in GLES2 fallback mode.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    #[cfg(feature = "gl")]
+    pub fn gl_force_gles2(mut self, force_gles2: bool) -> Self {
+        self.config.gl_force_gles2 = force_gles2;
+        self
+    }
+
+    /// Share GL sampler objects between images with the same sampler state.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    #[cfg(feature = "gl")]
+    pub fn gl_use_sampler_objects(mut self, use_sampler_objects: bool) -> Self {
+        self.config.gl_use_sampler_objects = use_sampler_objects;
+        self
+    }
+
+    /// Filter cube map images across face edges.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    #[cfg(feature = "gl")]
+    pub fn gl_seamless_cubemaps(mut self, seamless: bool) -> Self {
+        self.config.gl_seamless_cubemaps = seamless;
+        self
+    }
+
+    /// Set the function used to look up OpenGL functions.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    #[cfg(feature = "gl")]
+    pub fn load_gl_symbol(mut self, lookup_fn: fn(&str) -> *const os::raw::c_void) -> Self {
+        self.config.load_gl_symbol = opengl::GlFunctionLookup::new(lookup_fn);
+        self
+    }
+
+    /// Set the `MTLDevice` to render with.
+    ///
+    /// This is only present when the `metal` feature is enabled.
+    #[cfg(feature = "metal")]
+    pub fn mtl_device(mut self, device: metal_sys::Device) -> Self {
+        self.config.mtl_device = device;
+        self
+    }
+
+    /// Set the callback returning the `MTLRenderPassDescriptor` of the
+    /// default framebuffer.
+    ///
+    /// This is only present when the `metal` feature is enabled.
+    #[cfg(feature = "metal")]
+    pub fn mtl_renderpass_descriptor_cb(
+        mut self,
+        cb: unsafe extern "C" fn() -> *const os::raw::c_void,
+    ) -> Self {
+        self.config.mtl_renderpass_descriptor_cb = Some(cb);
+        self
+    }
+
+    /// Set the callback returning the `MTLDrawable` of the default
+    /// framebuffer.
+    ///
+    /// This is only present when the `metal` feature is enabled.
+    #[cfg(feature = "metal")]
+    pub fn mtl_drawable_cb(mut self, cb: unsafe extern "C" fn() -> *const os::raw::c_void) -> Self {
+        self.config.mtl_drawable_cb = Some(cb);
+        self
+    }
+
+    /// Set the size of the global uniform buffer in bytes.
+    ///
+    /// This is only present when the `metal` feature is enabled.
+    #[cfg(feature = "metal")]
+    pub fn mtl_global_uniform_buffer_size(mut self, size: usize) -> Self {
+        self.config.mtl_global_uniform_buffer_size = size;
+        self
+    }
+
+    /// Set the number of slots in the sampler cache.
+    ///
+    /// This is only present when the `metal` feature is enabled.
+    #[cfg(feature = "metal")]
+    pub fn mtl_sampler_cache_size(mut self, size: usize) -> Self {
+        self.config.mtl_sampler_cache_size = size;
+        self
+    }
+
+    /// Set the `ID3D11Device` and `ID3D11DeviceContext` to render with.
+    ///
+    /// This is only present when the `d3d11` feature is enabled.
+    #[cfg(feature = "d3d11")]
+    pub fn d3d11_device(
+        mut self,
+        device: *const os::raw::c_void,
+        device_context: *const os::raw::c_void,
+    ) -> Self {
+        self.config.d3d11_device = device;
+        self.config.d3d11_device_context = device_context;
+        self
+    }
+
+    /// Set the callbacks returning the render target and depth-stencil
+    /// views of the default framebuffer.
+    ///
+    /// This is only present when the `d3d11` feature is enabled.
+    #[cfg(feature = "d3d11")]
+    pub fn d3d11_view_cbs(
+        mut self,
+        render_target_view_cb: unsafe extern "C" fn() -> *const os::raw::c_void,
+        depth_stencil_view_cb: unsafe extern "C" fn() -> *const os::raw::c_void,
+    ) -> Self {
+        self.config.d3d11_render_target_view_cb = Some(render_target_view_cb);
+        self.config.d3d11_depth_stencil_view_cb = Some(depth_stencil_view_cb);
+        self
+    }
+
+    /// Validate and return the configuration.
+    pub fn build(self) -> Result<Config, ConfigError> {
+        let pool_sizes = [
+            ("buffer", self.config.buffer_pool_size),
+            ("image", self.config.image_pool_size),
+            ("shader", self.config.shader_pool_size),
+            ("pipeline", self.config.pipeline_pool_size),
+            ("pass", self.config.pass_pool_size),
+        ];
+        for &(name, size) in &pool_sizes {
+            if size == 0 || size >= pool::MAX_POOL_SIZE {
+                return Err(ConfigError::InvalidPoolSize { pool: name, size });
+            }
+        }
+        Ok(self.config)
+    }
+}
+
+/// The value of skipped per-frame native resource fields when deserializing.
+#[cfg(all(feature = "serde", feature = "metal"))]
+fn null_inflight_ptrs() -> [*const os::raw::c_void; NUM_INFLIGHT_FRAMES] {
+    [ptr::null(); NUM_INFLIGHT_FRAMES]
+}
+
 /// Creation parameters for [`Buffer`] objects.
 ///
 /// Buffers with `Usage::Immutable` usage *must* fill the buffer
@@ -1196,39 +2473,198 @@ impl Default for Config {
 /// [`Buffer`]: struct.Buffer.html
 #[allow(missing_docs)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BufferDesc {
     pub size: usize,
     pub buffer_type: BufferType,
     pub usage: Usage,
     pub content: Vec<u8>,
     #[cfg(feature = "gl")] pub gl_buffers: [u32; NUM_INFLIGHT_FRAMES],
+    #[cfg_attr(feature = "serde", serde(skip, default = "null_inflight_ptrs"))]
     #[cfg(feature = "metal")] pub metal_buffers: [*const os::raw::c_void; NUM_INFLIGHT_FRAMES],
+    #[cfg_attr(feature = "serde", serde(skip, default = "ptr::null"))]
     #[cfg(feature = "d3d11")] pub d3d11_buffers: *const os::raw::c_void,
 }
 
 #[allow(missing_docs)]
-#[derive(Debug)]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SubimageContent {
     pub content: Vec<u8>,
 }
 
+impl SubimageContent {
+    /// Create subimage content from raw pixel bytes.
+    pub fn from_bytes(data: &[u8]) -> Self {
+        SubimageContent {
+            content: data.to_vec(),
+        }
+    }
+
+    /// Create subimage content for an image of `pixel_format` from a slice
+    /// of typed pixel components, such as `f32` values for a
+    /// `PixelFormat::RGBA32F` image.
+    ///
+    /// `T` is either a single component or a whole pixel, such as
+    /// `[f32; 4]`. Returns `None` if its size matches neither.
+    ///
+    /// The content is the native-endian byte representation of `data`,
+    /// so its length is `data.len() * size_of::<T>()`.
+    pub fn from_slice<T: Pod>(pixel_format: PixelFormat, data: &[T]) -> Option<Self> {
+        let size = ::std::mem::size_of::<T>();
+        if pixel_format.component_size() != Some(size)
+            && pixel_format.checked_bytesize() != Some(size)
+        {
+            warn!(
+                "SubimageContent::from_slice: {}-byte values don't match {:?} pixels",
+                size, pixel_format
+            );
+            return None;
+        }
+        Some(SubimageContent::from_bytes(slice_as_bytes(data)))
+    }
+}
+
+/// Plain old data that can be viewed as its underlying bytes, such as
+/// vertices, indices, pixel components and uniform blocks.
+///
+/// This is implemented for the primitive numeric types and arrays of them.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]` or `#[repr(transparent)]` types that
+/// contain no padding bytes and only fields that are `Pod` themselves, so
+/// that every byte of a value is initialized. A struct such as
+/// `{ a: f32, b: [f32; 3], c: u8 }` has trailing padding and must not
+/// implement `Pod`.
+#[allow(unsafe_code)]
+pub unsafe trait Pod: Copy + 'static {}
+
+/// Implement `Pod` for primitive numeric types and arrays of them.
+macro_rules! impl_pod {
+    ($($ty:ty),*) => {
+        $(
+            #[allow(unsafe_code)]
+            unsafe impl Pod for $ty {}
+        )*
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+#[allow(unsafe_code)]
+unsafe impl<T: Pod> Pod for [T; 2] {}
+#[allow(unsafe_code)]
+unsafe impl<T: Pod> Pod for [T; 3] {}
+#[allow(unsafe_code)]
+unsafe impl<T: Pod> Pod for [T; 4] {}
+#[allow(unsafe_code)]
+unsafe impl<T: Pod> Pod for [T; 8] {}
+#[allow(unsafe_code)]
+unsafe impl<T: Pod> Pod for [T; 12] {}
+#[allow(unsafe_code)]
+unsafe impl<T: Pod> Pod for [T; 16] {}
+
+/// View a slice of plain values as its underlying bytes.
+#[allow(unsafe_code)]
+fn slice_as_bytes<T: Pod>(data: &[T]) -> &[u8] {
+    // `Pod` types have no padding, so every byte of `data` is initialized.
+    unsafe {
+        ::std::slice::from_raw_parts(data.as_ptr() as *const u8, ::std::mem::size_of_val(data))
+    }
+}
+
+/// Plain data that can be uploaded as a shader uniform block with
+/// [`apply_uniforms()`].
+///
+/// For [`Pod`] types with a `#[repr(C)]` layout matching the uniform block,
+/// an empty implementation is enough:
+///
+/// ```no_run
+/// # use grafiska::{Pod, Uniforms};
+/// #[repr(C)]
+/// #[derive(Copy, Clone)]
+/// struct Params {
+///     mvp: [[f32; 4]; 4],
+///     tint: [f32; 4],
+/// }
+///
+/// // `Params` consists of `f32`s only, so it has no padding.
+/// unsafe impl Pod for Params {}
+/// impl Uniforms for Params {}
+/// ```
+///
+/// [`Pod`]: trait.Pod.html
+/// [`apply_uniforms()`]: struct.Context.html#method.apply_uniforms
+pub trait Uniforms: Pod {
+    /// The bytes of the uniform block.
+    fn as_bytes(&self) -> &[u8] {
+        slice_as_bytes(::std::slice::from_ref(self))
+    }
+
+    /// The size of the uniform block in bytes.
+    fn block_size() -> u32 {
+        ::std::mem::size_of::<Self>() as u32
+    }
+}
+
+/// Implement `Uniforms` for float vectors and matrices.
+macro_rules! impl_uniforms {
+    ($($ty:ty),*) => {
+        $(
+            impl Uniforms for $ty {}
+        )*
+    };
+}
+
+impl_uniforms!(
+    f32,
+    [f32; 2],
+    [f32; 3],
+    [f32; 4],
+    [f32; 8],
+    [f32; 12],
+    [f32; 16],
+    [[f32; 2]; 2],
+    [[f32; 3]; 3],
+    [[f32; 4]; 4]
+);
+
 /// The content of an image by way of a 2D array of [`SubimageContent`] structs.
 ///
-/// The first array dimension is the cubemap face and the second is the mipmap
-/// level.
+/// The first array dimension is the mipmap level and the second is the
+/// cubemap face. Images that aren't cubemaps only use face 0.
+///
+/// For 3D and array images, the content of a mipmap level holds all depth
+/// slices or array layers back to back, each slice being
+/// `PixelFormat::surface_pitch()` bytes at that level. Array images keep
+/// `depth_or_layers` layers in every mipmap level, while the depth of a 3D
+/// image halves with every level like its width and height.
 ///
 /// [`SubimageContent`]: struct.SubimageContent.html
 #[allow(missing_docs)]
-#[derive(Debug)]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ImageContent {
     pub subimage: [[SubimageContent; CUBEFACE_NUM]; MAX_MIPMAPS],
 }
 
+impl ImageContent {
+    /// Create image content with only the first mipmap level of the first
+    /// face filled in, as used by simple 2D textures.
+    pub fn single(data: SubimageContent) -> Self {
+        let mut content = ImageContent::default();
+        content.subimage[0][0] = data;
+        content
+    }
+}
+
 /// Creation parameters for [`Image`] objects.
 ///
 /// [`Image`]: struct.Image.html
 #[allow(missing_docs)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ImageDesc {
     pub image_type: ImageType,
     pub render_target: bool,
@@ -1241,18 +2677,65 @@ pub struct ImageDesc {
     pub sample_count: usize,
     pub min_filter: Filter,
     pub mag_filter: Filter,
+    /// The wrap mode in u direction.
+    ///
+    /// On GLES2, non-power-of-two images only support `ClampToEdge`, and
+    /// other wrap modes are replaced by it with a warning.
     pub wrap_u: Wrap,
+    /// The wrap mode in v direction, with the same GLES2 restriction as
+    /// `wrap_u`.
     pub wrap_v: Wrap,
+    /// The wrap mode in w direction. Only used by 3D images.
     pub wrap_w: Wrap,
     pub max_anisotropy: u32,
+    /// The lowest mipmap level of detail that is sampled. This must not
+    /// exceed `max_lod`. Ignored by GLES2.
     pub min_lod: f32,
+    /// The highest mipmap level of detail that is sampled. Ignored by GLES2.
     pub max_lod: f32,
+    /// The sources of the red, green, blue and alpha components when
+    /// sampling the image. Ignored by GLES2.
+    pub swizzle: [TextureSwizzle; 4],
     pub content: ImageContent,
     #[cfg(feature = "gl")] pub gl_textures: [u32; NUM_INFLIGHT_FRAMES],
+    #[cfg_attr(feature = "serde", serde(skip, default = "null_inflight_ptrs"))]
     #[cfg(feature = "metal")] pub mtl_textures: [*const os::raw::c_void; NUM_INFLIGHT_FRAMES],
+    #[cfg_attr(feature = "serde", serde(skip, default = "ptr::null"))]
     #[cfg(feature = "d3d11")] pub d3d11_texture: *const os::raw::c_void,
 }
 
+impl Default for ImageDesc {
+    fn default() -> Self {
+        ImageDesc {
+            image_type: ImageType::default(),
+            render_target: false,
+            width: 0,
+            height: 0,
+            depth_or_layers: 1,
+            num_mipmaps: 1,
+            usage: Usage::default(),
+            pixel_format: PixelFormat::default(),
+            sample_count: 1,
+            min_filter: Filter::default(),
+            mag_filter: Filter::default(),
+            wrap_u: Wrap::default(),
+            wrap_v: Wrap::default(),
+            wrap_w: Wrap::default(),
+            max_anisotropy: 1,
+            min_lod: 0.0,
+            max_lod: f32::MAX,
+            swizzle: TextureSwizzle::IDENTITY,
+            content: ImageContent::default(),
+            #[cfg(feature = "gl")]
+            gl_textures: [0; NUM_INFLIGHT_FRAMES],
+            #[cfg(feature = "metal")]
+            mtl_textures: [ptr::null(); NUM_INFLIGHT_FRAMES],
+            #[cfg(feature = "d3d11")]
+            d3d11_texture: ptr::null(),
+        }
+    }
+}
+
 #[allow(missing_docs)]
 #[derive(Debug)]
 pub struct ShaderUniformDesc {
@@ -1275,12 +2758,19 @@ pub struct ShaderImageDesc {
     pub image_type: ImageType,
 }
 
+/// A shader stage of a [`ShaderDesc`].
+///
+/// At least one of `source` and `byte_code` must be provided. Backends that
+/// can't use byte code, such as GL, compile `source` instead.
+///
+/// [`ShaderDesc`]: struct.ShaderDesc.html
 #[allow(missing_docs)]
 #[derive(Debug)]
 pub struct ShaderStageDesc {
+    /// The shader source code, or `""` if only byte code is provided.
     pub source: &'static str,
-    pub byte_code: *const u8,
-    pub byte_code_size: u32,
+    /// Precompiled shader byte code, such as a Metal library.
+    pub byte_code: Option<&'static [u8]>,
     pub entry: &'static str,
     pub uniform_blocks: [ShaderUniformBlockDesc; MAX_SHADERSTAGE_UBS],
     pub images: [ShaderImageDesc; MAX_SHADERSTAGE_IMAGES],
@@ -1298,9 +2788,17 @@ pub struct ShaderDesc {
 
 #[allow(missing_docs)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct VertexAttrDesc {
-    pub name: &'static str,
-    pub sem_name: &'static str,
+    /// The name of the attribute in the vertex shader.
+    ///
+    /// The names are `Cow<'static, str>` rather than `&'static str`, so
+    /// that deserialized descriptions can own them. String literals convert
+    /// with `.into()`.
+    pub name: Cow<'static, str>,
+    /// The semantic name of the attribute, for backends that bind
+    /// attributes by semantic rather than by name.
+    pub sem_name: Cow<'static, str>,
     pub sem_index: u32,
     pub offset: u32,
     pub format: VertexFormat,
@@ -1308,7 +2806,11 @@ pub struct VertexAttrDesc {
 
 #[allow(missing_docs)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct VertexLayoutDesc {
+    /// The distance between vertices in bytes, or 0 if the vertices are
+    /// tightly packed. If all attribute offsets are 0 too, the attributes
+    /// are tightly packed in order.
     pub stride: u32,
     pub step_func: VertexStep,
     pub step_rate: u32,
@@ -1317,6 +2819,7 @@ pub struct VertexLayoutDesc {
 
 #[allow(missing_docs)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct StencilState {
     pub fail_op: StencilOp,
     pub depth_fail_op: StencilOp,
@@ -1337,6 +2840,7 @@ impl Default for StencilState {
 
 #[allow(missing_docs)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DepthStencilState {
     pub stencil_front: StencilState,
     pub stencil_back: StencilState,
@@ -1344,7 +2848,9 @@ pub struct DepthStencilState {
     pub depth_write_enabled: bool,
     pub stencil_enabled: bool,
     pub stencil_read_mask: u8,
-    pub stencil_write_mask: ColorMask,
+    /// Bit mask of the stencil bits that stencil operations may write, so
+    /// `0xFF` enables writes to all 8 bits.
+    pub stencil_write_mask: u8,
     pub stencil_ref: u8,
 }
 
@@ -1357,7 +2863,7 @@ impl Default for DepthStencilState {
             depth_write_enabled: false,
             stencil_enabled: false,
             stencil_read_mask: 0,
-            stencil_write_mask: ColorMask::default(),
+            stencil_write_mask: 0,
             stencil_ref: 0,
         }
     }
@@ -1365,6 +2871,7 @@ impl Default for DepthStencilState {
 
 #[allow(missing_docs)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BlendState {
     pub enabled: bool,
     pub src_factor_rgb: BlendFactor,
@@ -1401,14 +2908,21 @@ impl Default for BlendState {
 
 #[allow(missing_docs)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RasterizerState {
     pub alpha_to_coverage_enabled: bool,
     pub cull_mode: CullMode,
     pub face_winding: FaceWinding,
+    pub polygon_mode: PolygonMode,
+    pub line_width: f32,
     pub sample_count: u32,
     pub depth_bias: f32,
     pub depth_bias_slope_scale: f32,
     pub depth_bias_clamp: f32,
+    /// Clamp the depth of fragments to the depth range instead of clipping
+    /// primitives at the near and far planes, as used for shadow maps. Not
+    /// supported by GLES, where it is ignored with a warning.
+    pub depth_clamp: bool,
 }
 
 impl Default for RasterizerState {
@@ -1417,10 +2931,13 @@ impl Default for RasterizerState {
             alpha_to_coverage_enabled: false,
             cull_mode: CullMode::default(),
             face_winding: FaceWinding::default(),
+            polygon_mode: PolygonMode::default(),
+            line_width: 1.0,
             sample_count: 0,
             depth_bias: 0f32,
             depth_bias_slope_scale: 0f32,
             depth_bias_clamp: 0f32,
+            depth_clamp: false,
         }
     }
 }
@@ -1430,13 +2947,27 @@ impl Default for RasterizerState {
 /// [`Pipeline`]: struct.Pipeline.html
 #[allow(missing_docs)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PipelineDesc {
     pub vertex_layouts: [VertexLayoutDesc; MAX_SHADERSTAGE_BUFFERS],
     pub shader: Shader,
     pub primitive_type: PrimitiveType,
     pub index_type: Option<IndexType>,
+    /// Restart the strip at each index with the maximum value of the index
+    /// type, i.e. `0xFFFF` for `UInt16` and `0xFFFF_FFFF` for `UInt32`.
+    ///
+    /// Only valid for `LineStrip` and `TriangleStrip` primitives.
+    pub primitive_restart: bool,
     pub depth_stencil: DepthStencilState,
     pub blend: BlendState,
+    /// Separate blend states for each color attachment, overriding the
+    /// blending and color write mask of `blend` for multiple render
+    /// targets. The other `blend` settings, such as the blend color and
+    /// attachment count, are shared by all attachments.
+    ///
+    /// Requires `Feature::MultipleRenderTarget`, and GL 4.0 or GLES 3.2
+    /// for the GL backend.
+    pub attachment_blends: Option<[BlendState; MAX_COLOR_ATTACHMENTS]>,
     pub rasterizer: RasterizerState,
 }
 
@@ -1455,6 +2986,22 @@ pub struct AttachmentDesc {
     /// If the image is a cube map, array texture or 3D texture,
     /// the face index, array layer or depth slice to render.
     pub index: usize,
+    /// Whether a multisampled color attachment is resolved into the image
+    /// at the end of the pass. If `false`, the rendering results are only
+    /// kept in the multisampled buffer. This is ignored for the
+    /// depth-stencil attachment. Defaults to `true`.
+    pub resolve: bool,
+}
+
+impl Default for AttachmentDesc {
+    fn default() -> Self {
+        AttachmentDesc {
+            image: Image::default(),
+            mip_level: 0,
+            index: 0,
+            resolve: true,
+        }
+    }
 }
 
 /// Creation parameters for a [`Pass`] object.
@@ -1463,20 +3010,176 @@ pub struct AttachmentDesc {
 ///
 /// Pass images must fulfill the following requirements:
 ///
+/// * There must be at least one color attachment or a depth-stencil
+///   attachment. Passes without color attachments only render depth and
+///   stencil, such as for shadow maps.
 /// * Must be created as a render target (`ImageDesc.render_target` must be `true`).
 /// * All images must be the same size.
 /// * All images must have the same sample count.
 /// * All color attachment images must have the same pixel format.
+/// * Multisampled images require `Feature::MSAARenderTargets`.
+/// * More than one color attachment requires `Feature::MultipleRenderTarget`.
+/// * The mip level of an attachment must exist in its image, and the index
+///   must be below 6 for cube maps, below the number of layers or slices
+///   for array and 3D images, and 0 for 2D images.
 ///
 /// [`Pass`]: struct.Pass.html
 #[derive(Debug)]
 pub struct PassDesc {
-    /// Up to `MAX_COLOR_ATTACHMENTS` color attachments.
+    /// Up to `MAX_COLOR_ATTACHMENTS` color attachments. Attachments with
+    /// the default, invalid image handle are unused, and must follow the
+    /// used ones.
     pub color_attachments: [AttachmentDesc; MAX_COLOR_ATTACHMENTS],
     /// An optional depth-stencil attachment.
     pub depth_stencil_attachment: Option<AttachmentDesc>,
 }
 
+impl PassDesc {
+    /// The number of used color attachments, which precede the unused ones.
+    pub fn num_color_attachments(&self) -> usize {
+        self.color_attachments
+            .iter()
+            .take_while(|att| att.image.is_valid())
+            .count()
+    }
+}
+
+/// Check the attachments of a pass description against the images that
+/// `lookup_image` returns for them and the backend features reported by
+/// `has_feature`.
+fn validate_pass_attachments<'a, L, F>(
+    desc: &PassDesc,
+    lookup_image: L,
+    has_feature: F,
+) -> Result<(), PassValidationError>
+where
+    L: Fn(&Image) -> Option<&'a backend::ImageResource>,
+    F: Fn(Feature) -> bool,
+{
+    let lookup = |att: &AttachmentDesc, which: PassAttachment| {
+        lookup_image(&att.image)
+            .filter(|img| img.slot.state == ResourceState::Valid)
+            .ok_or(PassValidationError::InvalidImage(which))
+    };
+    let check_subimage =
+        |att: &AttachmentDesc, img: &backend::ImageResource, which: PassAttachment| {
+            if att.mip_level >= img.num_mipmaps.max(1) {
+                return Err(PassValidationError::InvalidMipLevel(which));
+            }
+            let num_indices = match img.image_type {
+                ImageType::Texture2D => 1,
+                ImageType::Cube => 6,
+                ImageType::Texture3D | ImageType::Array => img.depth,
+            };
+            if att.index >= num_indices {
+                return Err(PassValidationError::InvalidIndex(which));
+            }
+            Ok(())
+        };
+
+    let num_color_attachments = desc.num_color_attachments();
+    if let Some(index) = desc.color_attachments[num_color_attachments..]
+        .iter()
+        .position(|att| att.image.is_valid())
+    {
+        let which = PassAttachment::Color(num_color_attachments + index);
+        return Err(PassValidationError::AttachmentGap(which));
+    }
+    if num_color_attachments > 1 && !has_feature(Feature::MultipleRenderTarget) {
+        return Err(PassValidationError::MRTNotSupported);
+    }
+
+    let mut first = None;
+    for (index, att) in desc.color_attachments[..num_color_attachments]
+        .iter()
+        .enumerate()
+    {
+        let which = PassAttachment::Color(index);
+        let img = lookup(att, which)?;
+        if !img.render_target {
+            return Err(PassValidationError::NotRenderTarget(which));
+        }
+        check_subimage(att, img, which)?;
+        let first = *first.get_or_insert(img);
+        if img.width != first.width || img.height != first.height {
+            return Err(PassValidationError::SizeMismatch(which));
+        }
+        if img.sample_count != first.sample_count {
+            return Err(PassValidationError::SampleCountMismatch(which));
+        }
+        if img.pixel_format != first.pixel_format {
+            return Err(PassValidationError::ColorFormatMismatch(which));
+        }
+        if img.sample_count > 1 && !has_feature(Feature::MSAARenderTargets) {
+            return Err(PassValidationError::MSAANotSupported(which));
+        }
+        if !att.resolve && img.sample_count <= 1 {
+            return Err(PassValidationError::NoStoreTarget(which));
+        }
+    }
+
+    match desc.depth_stencil_attachment {
+        Some(ref att) => {
+            let which = PassAttachment::DepthStencil;
+            let img = lookup(att, which)?;
+            if !img.render_target {
+                return Err(PassValidationError::NotRenderTarget(which));
+            }
+            check_subimage(att, img, which)?;
+            // Depth-only passes have no color attachment to match.
+            if let Some(first) = first {
+                if img.width != first.width || img.height != first.height {
+                    return Err(PassValidationError::SizeMismatch(which));
+                }
+                if img.sample_count != first.sample_count {
+                    return Err(PassValidationError::SampleCountMismatch(which));
+                }
+            }
+        }
+        None if first.is_none() => return Err(PassValidationError::NoColorAttachments),
+        None => {}
+    }
+    Ok(())
+}
+
+/// The total byte size of the content of an image, or `None` if its pixel
+/// format has no byte size. See `Context::query_image_content_size()`.
+fn image_content_size(res: &backend::ImageResource) -> Option<usize> {
+    if !res.pixel_format.is_compressed_pixel_format()
+        && res.pixel_format.checked_bytesize().is_none()
+    {
+        return None;
+    }
+    Some(
+        (0..res.num_mipmaps)
+            .map(|mip| {
+                let width = (res.width >> mip).max(1);
+                let height = (res.height >> mip).max(1);
+                let num_slices = match res.image_type {
+                    ImageType::Texture2D => 1,
+                    ImageType::Cube => CUBEFACE_NUM,
+                    ImageType::Texture3D => (res.depth >> mip).max(1),
+                    ImageType::Array => res.depth,
+                };
+                res.pixel_format.surface_pitch(width, height) * num_slices
+            })
+            .sum(),
+    )
+}
+
+/// Mark all resources of a pool as `ResourceState::Failed`, given the slot
+/// of a resource.
+#[cfg(feature = "gl")]
+fn fail_resources<R, F>(pool: &mut pool::Pool<R>, slot: F)
+where
+    R: ResourceHandle,
+    F: Fn(&mut R::Resource) -> &mut pool::Slot,
+{
+    for res in pool.resources_mut() {
+        slot(res).state = ResourceState::Failed;
+    }
+}
+
 /// Internal state of a grafiska context.
 pub struct Context {
     buffer_pool: pool::Pool<Buffer>,
@@ -1485,10 +3188,16 @@ pub struct Context {
     pipeline_pool: pool::Pool<Pipeline>,
     pass_pool: pool::Pool<Pass>,
     frame_index: u32,
+    frame_stats: FrameStats,
+    last_frame_stats: FrameStats,
     current_pass: Option<Pass>,
     current_pipeline: Option<Pipeline>,
+    in_pass: bool,
     pass_valid: bool,
     next_draw_valid: bool,
+    validation: bool,
+    headless: bool,
+    trace_hook: Option<TraceHook>,
     backend: backend::Backend,
 }
 
@@ -1497,25 +3206,349 @@ impl Context {
     ///
     /// This must be performed after creating a window and a 3D API
     /// context/device.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is invalid. Use [`try_new()`] to handle
+    /// this instead.
+    ///
+    /// [`try_new()`]: #method.try_new
     pub fn new(desc: Config) -> Self {
-        Context {
-            buffer_pool: pool::Pool::<Buffer>::new(desc.buffer_pool_size),
-            image_pool: pool::Pool::<Image>::new(desc.image_pool_size),
-            shader_pool: pool::Pool::<Shader>::new(desc.shader_pool_size),
-            pipeline_pool: pool::Pool::<Pipeline>::new(desc.pipeline_pool_size),
-            pass_pool: pool::Pool::<Pass>::new(desc.pass_pool_size),
+        match Context::try_new(desc) {
+            Ok(ctx) => ctx,
+            Err(err) => panic!("invalid grafiska configuration: {}", err),
+        }
+    }
+
+    /// Initialize the Grafiska library with the default configuration,
+    /// looking up OpenGL functions with `lookup_fn`.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    #[cfg(feature = "gl")]
+    pub fn with_gl_loader(lookup_fn: fn(&str) -> *const os::raw::c_void) -> Self {
+        Context::new(Config {
+            load_gl_symbol: opengl::GlFunctionLookup::new(lookup_fn),
+            ..Config::default()
+        })
+    }
+
+    /// Initialize the Grafiska library with the default configuration,
+    /// rendering with the Metal `device` and obtaining the render pass
+    /// descriptor and drawable of the default framebuffer from the given
+    /// callbacks.
+    ///
+    /// This is only present when the `metal` feature is enabled.
+    #[cfg(feature = "metal")]
+    pub fn with_metal_device(
+        device: metal_sys::Device,
+        renderpass_descriptor_cb: unsafe extern "C" fn() -> *const os::raw::c_void,
+        drawable_cb: unsafe extern "C" fn() -> *const os::raw::c_void,
+    ) -> Self {
+        Context::new(Config {
+            mtl_device: device,
+            mtl_renderpass_descriptor_cb: Some(renderpass_descriptor_cb),
+            mtl_drawable_cb: Some(drawable_cb),
+            ..Config::default()
+        })
+    }
+
+    /// Initialize the Grafiska library, failing if the configuration is
+    /// invalid.
+    ///
+    /// This must be performed after creating a window and a 3D API
+    /// context/device.
+    pub fn try_new(mut desc: Config) -> Result<Self, ConfigError> {
+        fn make_pool<R: ResourceHandle>(
+            name: &'static str,
+            size: usize,
+            desc: &Config,
+        ) -> Result<pool::Pool<R>, ConfigError> {
+            let pool = if desc.debug_pool_lifo {
+                pool::Pool::new_lifo(size)
+            } else {
+                pool::Pool::new(size)
+            };
+            let pool = pool.map_err(|err| match err {
+                pool::PoolError::InvalidSize(size) => {
+                    ConfigError::InvalidPoolSize { pool: name, size }
+                }
+            })?;
+            if desc.panic_on_invalid_handle {
+                Ok(pool.panic_on_invalid(name))
+            } else {
+                Ok(pool)
+            }
+        }
+
+        Ok(Context {
+            buffer_pool: make_pool("buffer", desc.buffer_pool_size, &desc)?,
+            image_pool: make_pool("image", desc.image_pool_size, &desc)?,
+            shader_pool: make_pool("shader", desc.shader_pool_size, &desc)?,
+            pipeline_pool: make_pool("pipeline", desc.pipeline_pool_size, &desc)?,
+            pass_pool: make_pool("pass", desc.pass_pool_size, &desc)?,
             frame_index: 1,
+            frame_stats: FrameStats::default(),
+            last_frame_stats: FrameStats::default(),
             current_pass: None,
             current_pipeline: None,
+            in_pass: false,
             pass_valid: false,
             next_draw_valid: false,
+            validation: desc.validation,
+            headless: desc.headless,
+            trace_hook: desc.trace_hook.take(),
             backend: backend::Backend::new(desc),
+        })
+    }
+
+    fn trace(&self, event: TraceEvent) {
+        if let Some(ref trace_hook) = self.trace_hook {
+            (trace_hook.hook)(event);
+        }
+    }
+
+    /// Check that the images of a pass description fulfill the requirements
+    /// listed for [`PassDesc`].
+    ///
+    /// This is performed when creating a pass.
+    ///
+    /// [`PassDesc`]: struct.PassDesc.html
+    pub fn validate_pass_desc(&self, desc: &PassDesc) -> Result<(), PassValidationError> {
+        validate_pass_attachments(
+            desc,
+            |image| self.image_pool.lookup(image),
+            |feature| self.query_feature(feature),
+        )
+    }
+
+    fn validate_image_limits(&self, desc: &ImageDesc) -> bool {
+        let limits = self.backend.query_limits();
+        let depth_or_layers = desc.depth_or_layers as usize;
+        let (max_size, valid) = match desc.image_type {
+            ImageType::Texture2D => (limits.max_image_size_2d, true),
+            ImageType::Cube => (limits.max_image_size_cube, true),
+            ImageType::Texture3D => (
+                limits.max_image_size_3d,
+                depth_or_layers <= limits.max_image_size_3d,
+            ),
+            ImageType::Array => (
+                limits.max_image_size_2d,
+                depth_or_layers <= limits.max_image_array_layers.min(MAX_TEXTUREARRAY_LAYERS),
+            ),
+        };
+        if !valid || desc.width > max_size || desc.height > max_size {
+            warn!(
+                "make_image: {}x{}x{} {:?} image exceeds the device limits",
+                desc.width, desc.height, desc.depth_or_layers, desc.image_type
+            );
+            return false;
+        }
+        if desc.render_target && desc.sample_count > 1 {
+            let max_sample_count = self.query_max_sample_count(desc.pixel_format);
+            if !desc.sample_count.is_power_of_two() || desc.sample_count > max_sample_count {
+                warn!(
+                    "make_image: sample count {} is not supported for {:?}, the maximum is {}",
+                    desc.sample_count, desc.pixel_format, max_sample_count
+                );
+                return false;
+            }
+        }
+        true
+    }
+
+    fn validate_draw_state(&self, pip: &backend::PipelineResource, ds: &DrawState) -> bool {
+        let is_valid_buffer = |buf: &Buffer| {
+            self.buffer_pool
+                .lookup(buf)
+                .is_some_and(|b| b.slot.state == ResourceState::Valid)
+        };
+        for (i, buf) in ds.vertex_buffers.iter().enumerate() {
+            if pip.uses_vertex_buffer(i) && !is_valid_buffer(buf) {
+                warn!(
+                    "apply_draw_state: pipeline needs a valid vertex buffer in slot {}",
+                    i
+                );
+                return false;
+            }
+        }
+        match (pip.index_type(), ds.index_buffer) {
+            (Some(_), None) => {
+                warn!("apply_draw_state: pipeline with index type needs an index buffer");
+                return false;
+            }
+            (None, Some(_)) => {
+                warn!("apply_draw_state: pipeline without index type can't use an index buffer");
+                return false;
+            }
+            (Some(_), Some(ref buf)) if !is_valid_buffer(buf) => {
+                warn!("apply_draw_state: index buffer is not valid");
+                return false;
+            }
+            _ => {}
+        }
+        let stages = [
+            (ShaderStage::VS, &ds.vs_images),
+            (ShaderStage::FS, &ds.fs_images),
+        ];
+        for &(stage, images) in &stages {
+            for (i, img) in images.iter().enumerate() {
+                let expected = pip.shader_image_type(stage, i);
+                let actual = self
+                    .image_pool
+                    .lookup(img)
+                    .filter(|res| res.slot.state == ResourceState::Valid)
+                    .map(|res| res.image_type);
+                if expected != actual {
+                    warn!(
+                        "apply_draw_state: {:?} image slot {} expects {:?}, got {:?}",
+                        stage, i, expected, actual
+                    );
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Find a handle of a draw state that refers to a destroyed resource,
+    /// returning the name of its field.
+    fn find_stale_handle(&self, ds: &DrawState) -> Option<String> {
+        if ds.pipeline.is_valid() && !self.pipeline_pool.contains(&ds.pipeline) {
+            return Some("pipeline".to_owned());
+        }
+        for (i, buf) in ds.vertex_buffers.iter().enumerate() {
+            if buf.is_valid() && !self.buffer_pool.contains(buf) {
+                return Some(format!("vertex_buffers[{}]", i));
+            }
+        }
+        if let Some(ref buf) = ds.index_buffer {
+            if buf.is_valid() && !self.buffer_pool.contains(buf) {
+                return Some("index_buffer".to_owned());
+            }
+        }
+        for &(name, images) in &[("vs_images", &ds.vs_images), ("fs_images", &ds.fs_images)] {
+            for (i, img) in images.iter().enumerate() {
+                if img.is_valid() && !self.image_pool.contains(img) {
+                    return Some(format!("{}[{}]", name, i));
+                }
+            }
+        }
+        None
+    }
+
+    fn validate_pipeline_limits(&self, desc: &PipelineDesc) -> bool {
+        let limits = self.backend.query_limits();
+        let num_attrs = desc
+            .vertex_layouts
+            .iter()
+            .flat_map(|layout| layout.attrs.iter())
+            .filter(|attr| is_used_vertex_attr(attr))
+            .count();
+        if num_attrs > limits.max_vertex_attributes {
+            warn!(
+                "make_pipeline: {} vertex attributes exceed the device limit of {}",
+                num_attrs, limits.max_vertex_attributes
+            );
+            return false;
+        }
+        let num_color_attachments = desc.blend.color_attachment_count as usize;
+        if num_color_attachments > MAX_COLOR_ATTACHMENTS {
+            warn!(
+                "make_pipeline: {} color attachments exceed the maximum of {}",
+                num_color_attachments, MAX_COLOR_ATTACHMENTS
+            );
+            return false;
+        }
+        if desc.attachment_blends.is_some() && !self.query_feature(Feature::MultipleRenderTarget) {
+            warn!("make_pipeline: per-attachment blend state requires multiple render targets");
+            return false;
+        }
+        let sample_count = desc.rasterizer.sample_count as usize;
+        if sample_count > 1
+            && (!sample_count.is_power_of_two() || sample_count > limits.max_sample_count)
+        {
+            warn!(
+                "make_pipeline: sample count {} is not supported, the maximum is {}",
+                sample_count, limits.max_sample_count
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Destroy all resources that are still alive, keeping the context
+    /// usable for creating new resources.
+    ///
+    /// Destroying the old handles afterwards is a no-op. This must not be
+    /// called within a rendering pass.
+    pub fn destroy_all(&mut self) {
+        if self.in_pass {
+            warn!("destroy_all: can't destroy resources within a rendering pass");
+            return;
         }
+        self.destroy_pools();
+        self.current_pipeline = None;
+        // GL may reuse the names of the deleted objects for new resources.
+        self.backend.reset_state_cache();
     }
 
     /// Shutdown the Grafiska library.
+    ///
+    /// All resources that are still alive are destroyed. Destroying their
+    /// handles afterwards is a no-op.
     pub fn shutdown(&mut self) {
-        unimplemented!()
+        self.destroy_pools();
+        self.backend.destroy_sampler_cache();
+    }
+
+    fn destroy_pools(&mut self) {
+        for pass in self.pass_pool.clear() {
+            self.backend.destroy_pass(pass);
+        }
+        self.pipeline_pool.clear();
+        for shd in self.shader_pool.clear() {
+            self.backend.destroy_shader(shd);
+        }
+        for img in self.image_pool.clear() {
+            self.backend.destroy_image(img);
+        }
+        for buf in self.buffer_pool.clear() {
+            self.backend.destroy_buffer(buf);
+        }
+    }
+
+    /// Handle the loss of the GL context, such as a WebGL context being
+    /// lost when the GPU is reset.
+    ///
+    /// All resources are marked as `ResourceState::Failed`, so rendering
+    /// with them is silently skipped. Once a new context is available,
+    /// call [`recreate()`] and create the resources again.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    ///
+    /// [`recreate()`]: #method.recreate
+    #[cfg(feature = "gl")]
+    pub fn on_context_lost(&mut self) {
+        fail_resources(&mut self.buffer_pool, |buf| &mut buf.slot);
+        fail_resources(&mut self.image_pool, |img| &mut img.slot);
+        fail_resources(&mut self.shader_pool, |shd| &mut shd.slot);
+        fail_resources(&mut self.pipeline_pool, |pip| &mut pip.slot);
+        fail_resources(&mut self.pass_pool, |pass| &mut pass.slot);
+        self.current_pass = None;
+        self.current_pipeline = None;
+        self.in_pass = false;
+        self.pass_valid = false;
+        self.next_draw_valid = false;
+        self.backend.on_context_lost();
+    }
+
+    /// Reinitialize the GL backend after the context was lost and a new
+    /// one has been made current.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    #[cfg(feature = "gl")]
+    pub fn recreate(&mut self) {
+        self.backend.recreate();
     }
 
     /// Test to see if a feature is supported by the rendering backend.
@@ -1523,36 +3556,610 @@ impl Context {
         self.backend.query_feature(feature)
     }
 
+    /// Query all the features that are supported by the rendering backend.
+    pub fn query_features(&self) -> Vec<Feature> {
+        ALL_FEATURES
+            .iter()
+            .cloned()
+            .filter(|&feature| self.query_feature(feature))
+            .collect()
+    }
+
+    /// Query whether a feature is supported and, if not, why.
+    pub fn query_feature_detail(&self, feature: Feature) -> FeatureSupport {
+        self.backend.query_feature_detail(feature)
+    }
+
+    /// Map a texture coordinate for sampling a render target, given with a
+    /// top-left origin where `v = 0` is the top edge of the rendered image,
+    /// to the origin of the rendering backend.
+    ///
+    /// This flips `v` on backends with `Feature::OriginBottomLeft`, such as
+    /// GL, so that applications don't need to flip per backend.
+    pub fn project_texcoord(&self, uv: [f32; 2]) -> [f32; 2] {
+        if self.flip_viewport_y(true) {
+            [uv[0], 1.0 - uv[1]]
+        } else {
+            uv
+        }
+    }
+
+    /// Whether sampling the images rendered by `pass` needs a flipped `v`
+    /// texture coordinate to show them upright.
+    ///
+    /// The rule is: images rendered by an offscreen pass on a backend with
+    /// `Feature::OriginBottomLeft`, such as GL, are stored bottom row first
+    /// and need a flip when sampled with a top-left texture origin. Nothing
+    /// needs a flip on backends with `Feature::OriginTopLeft`, such as
+    /// Metal. The default framebuffer is never sampled and has no `Pass`
+    /// handle, so it never needs a flip.
+    ///
+    /// Returns `false` for passes that don't exist.
+    pub fn pass_needs_y_flip(&self, pass: Pass) -> bool {
+        self.pass_pool.contains(&pass) && self.query_feature(Feature::OriginBottomLeft)
+    }
+
+    /// Whether y coordinates given with a top-left origin, or a bottom-left
+    /// one if `origin_top_left` is `false`, must be flipped to match the
+    /// origin of the rendering backend.
+    fn flip_viewport_y(&self, origin_top_left: bool) -> bool {
+        origin_top_left != self.query_feature(Feature::OriginTopLeft)
+    }
+
+    /// Query the resource limits of the rendering device.
+    pub fn query_limits(&self) -> Limits {
+        self.backend.query_limits()
+    }
+
+    /// Query the maximum sample count of multisampled render targets with
+    /// the pixel format `fmt`, or 1 if they can't be multisampled.
+    ///
+    /// Images and pipelines with a sample count that isn't a power of two
+    /// or exceeds this fail to be created.
+    pub fn query_max_sample_count(&self, fmt: PixelFormat) -> usize {
+        if self.query_pixelformat(fmt).msaa {
+            self.query_limits().max_sample_count
+        } else {
+            1
+        }
+    }
+
+    /// Query the state of the resource of any type that `handle` refers to.
+    ///
+    /// Stale and discarded handles report `ResourceState::Initial`.
+    pub fn query_state<H: ResourceHandle>(&self, handle: H) -> ResourceState {
+        handle.state(self)
+    }
+
+    /// Query the properties of a buffer.
+    ///
+    /// Returns `None` if the handle does not refer to a live buffer.
+    pub fn query_buffer_info(&self, buf: Buffer) -> Option<BufferInfo> {
+        self.buffer_pool.lookup(&buf).map(|res| BufferInfo {
+            size: res.size,
+            buffer_type: res.buffer_type,
+            usage: res.usage,
+            state: res.slot.state,
+        })
+    }
+
+    /// Query the properties of an image.
+    ///
+    /// Returns `None` if the handle does not refer to a live image.
+    pub fn query_image_info(&self, img: Image) -> Option<ImageInfo> {
+        self.image_pool.lookup(&img).map(|res| ImageInfo {
+            image_type: res.image_type,
+            width: res.width,
+            height: res.height,
+            depth_or_layers: res.depth,
+            pixel_format: res.pixel_format,
+            num_mipmaps: res.num_mipmaps,
+            sample_count: res.sample_count,
+            render_target: res.render_target,
+            usage: res.usage,
+            state: res.slot.state,
+        })
+    }
+
+    /// Query the width, height and depth (or number of array layers) of
+    /// an image, such as to set the viewport when rendering into it.
+    ///
+    /// Returns `None` if the handle does not refer to a live image.
+    pub fn query_image_size(&self, img: Image) -> Option<(usize, usize, usize)> {
+        self.image_pool
+            .lookup(&img)
+            .map(|res| (res.width, res.height, res.depth))
+    }
+
+    /// Query the number of mipmap levels of an image.
+    ///
+    /// Returns `None` if the handle does not refer to a live image.
+    pub fn query_image_num_mipmaps(&self, img: Image) -> Option<usize> {
+        self.image_pool.lookup(&img).map(|res| res.num_mipmaps)
+    }
+
+    /// Query the total byte size of the content of an image, summed over
+    /// all mipmap levels and cubemap faces, 3D slices or array layers, as
+    /// laid out in `ImageContent`.
+    ///
+    /// Returns `None` if the handle does not refer to a live image, or if
+    /// the image has a pixel format without a byte size (such as a depth
+    /// format), which can't be given initial content.
+    pub fn query_image_content_size(&self, img: Image) -> Option<usize> {
+        self.image_pool.lookup(&img).and_then(image_content_size)
+    }
+
+    /// Query the size in bytes of uniform block `ub_index` of a shader
+    /// stage, which is the size that `apply_uniform_block()` expects.
+    ///
+    /// Returns `None` if the handle does not refer to a live shader or the
+    /// stage has no such uniform block.
+    pub fn query_uniform_block_size(
+        &self,
+        shd: Shader,
+        stage: ShaderStage,
+        ub_index: u32,
+    ) -> Option<u32> {
+        self.shader_pool
+            .lookup(&shd)
+            .and_then(|res| res.uniform_block_size(stage, ub_index as usize))
+            .map(|size| size as u32)
+    }
+
+    /// Query the named vertex attributes of a pipeline and the shader
+    /// attribute locations they were bound to.
+    ///
+    /// A location of -1 means that the shader doesn't use the attribute,
+    /// usually because the shader compiler optimized it out. Only the GL
+    /// backend binds attributes by name, so this is empty for other backends
+    /// and for pipelines that don't exist.
+    pub fn query_pipeline_attrib_locations(&self, pip: Pipeline) -> Vec<(String, i32)> {
+        self.pipeline_pool
+            .lookup(&pip)
+            .map_or_else(Vec::new, |res| res.attrib_locations().to_vec())
+    }
+
+    /// Query the images that a pass renders into, such as to sample them in
+    /// a later pass.
+    ///
+    /// Returns `None` if the handle does not refer to a live pass.
+    pub fn query_pass_attachments(&self, pass: Pass) -> Option<PassAttachments> {
+        self.pass_pool.lookup(&pass).map(|res| res.attachments())
+    }
+
+    /// Whether `pass` renders into multisampled render targets, which are
+    /// resolved into the attached images at the end of the pass unless
+    /// their `AttachmentDesc.resolve` is `false`.
+    ///
+    /// Returns `false` for passes that don't exist.
+    pub fn query_pass_msaa(&self, pass: Pass) -> bool {
+        self.pass_pool
+            .lookup(&pass)
+            .filter(|res| res.slot.state == ResourceState::Valid)
+            .and_then(|res| res.color_atts.first())
+            .is_some_and(|att| att.image.sample_count > 1)
+    }
+
+    /// Query how images of a pixel format can be used on the rendering
+    /// device.
+    pub fn query_pixelformat(&self, fmt: PixelFormat) -> PixelFormatInfo {
+        self.backend.query_pixelformat(fmt)
+    }
+
+    /// Query the index of the current frame, which starts at 1 and is
+    /// incremented by `commit()`.
+    ///
+    /// Buffers and images can only be updated once per frame index.
+    pub fn query_frame_index(&self) -> u32 {
+        self.frame_index
+    }
+
+    /// Query the size of a buffer in bytes.
+    ///
+    /// Returns `None` if the handle does not refer to a live buffer.
+    pub fn query_buffer_size(&self, buf: Buffer) -> Option<usize> {
+        self.buffer_pool.lookup(&buf).map(|res| res.size)
+    }
+
+    /// Query the byte offset at which the next `append_buffer()` call
+    /// writes into a buffer during the current frame.
+    ///
+    /// This is 0 at the start of each frame. Returns `None` if the handle
+    /// does not refer to a live buffer.
+    pub fn query_buffer_append_pos(&self, buf: Buffer) -> Option<u32> {
+        self.buffer_pool
+            .lookup(&buf)
+            .map(|res| res.append_pos(self.frame_index) as u32)
+    }
+
+    /// Query whether updating a buffer now would exceed the limit of one
+    /// update per frame, because it was already updated during the current
+    /// frame index.
+    ///
+    /// Returns `false` for invalid handles.
+    pub fn query_buffer_will_overflow_update(&self, buf: Buffer) -> bool {
+        self.buffer_pool
+            .lookup(&buf)
+            .is_some_and(|res| res.upd_frame_index() == self.frame_index)
+    }
+
+    /// Query whether `update_buffer()` would update a buffer now, instead of
+    /// ignoring the update.
+    ///
+    /// Updates are not allowed for immutable buffers, or for buffers that
+    /// were already updated during the current frame index. Returns `false`
+    /// for invalid handles.
+    pub fn query_update_allowed(&self, buf: Buffer) -> bool {
+        self.buffer_pool.lookup(&buf).is_some_and(|res| {
+            res.slot.state == ResourceState::Valid
+                && res.usage != Usage::Immutable
+                && res.upd_frame_index() != self.frame_index
+        })
+    }
+
+    /// Query the rendering work of the last frame that was finished by
+    /// `commit()`.
+    pub fn query_frame_stats(&self) -> FrameStats {
+        self.last_frame_stats
+    }
+
+    /// Query the name and version of the rendering backend.
+    pub fn query_backend_info(&self) -> BackendInfo {
+        self.backend.query_backend_info()
+    }
+
+    /// Query the range of line widths supported by the rendering backend.
+    ///
+    /// The `line_width` of a pipeline's `RasterizerState` is clamped to
+    /// this range when drawing lines.
+    pub fn query_line_width_range(&self) -> (f32, f32) {
+        self.backend.query_line_width_range()
+    }
+
     /// If you call directly into the underlying 3D API, this must be called
     /// prior to using Grafiska functions again.
     pub fn reset_state_cache(&mut self) {
         self.backend.reset_state_cache();
     }
 
+    /// Start a scope in which you call directly into OpenGL, returning a
+    /// guard that calls [`reset_state_cache()`] when it is dropped.
+    ///
+    /// ```ignore
+    /// {
+    ///     let _guard = ctx.external_gl_scope();
+    ///     // Raw GL calls...
+    /// }
+    /// ```
+    ///
+    /// The state cache is only reset at the end of the scope, so Grafiska
+    /// functions must not be used while the guard is alive. The guard
+    /// borrows the context mutably to enforce this.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    ///
+    /// [`reset_state_cache()`]: #method.reset_state_cache
+    #[cfg(feature = "gl")]
+    pub fn external_gl_scope(&mut self) -> ExternalGlGuard<'_> {
+        ExternalGlGuard { ctx: self }
+    }
+
+    /// Create an immutable 2D [`Image`] from tightly packed RGBA8 pixels,
+    /// with a single mipmap level and linear filtering.
+    ///
+    /// Returns `None` if `pixels` is not exactly `width * height * 4`
+    /// bytes long, or if that size overflows.
+    ///
+    /// [`Image`]: struct.Image.html
+    pub fn make_image_simple(
+        &mut self,
+        width: usize,
+        height: usize,
+        pixels: &[u8],
+    ) -> Option<Image> {
+        let expected = match width
+            .checked_mul(height)
+            .and_then(|num_pixels| num_pixels.checked_mul(PixelFormat::RGBA8.bytesize()))
+        {
+            Some(expected) => expected,
+            None => {
+                warn!("make_image_simple: {}x{} image is too large", width, height);
+                return None;
+            }
+        };
+        if pixels.len() != expected {
+            warn!(
+                "make_image_simple: expected {} bytes of pixel data for {}x{}, got {}",
+                expected,
+                width,
+                height,
+                pixels.len()
+            );
+            return None;
+        }
+        let desc = ImageDesc {
+            image_type: ImageType::Texture2D,
+            width,
+            height,
+            usage: Usage::Immutable,
+            pixel_format: PixelFormat::RGBA8,
+            min_filter: Filter::Linear,
+            mag_filter: Filter::Linear,
+            content: ImageContent::single(SubimageContent::from_bytes(pixels)),
+            ..Default::default()
+        };
+        Image::make(self, desc)
+    }
+
+    /// Create an immutable [`Buffer`] from a slice of plain values, such as
+    /// vertices or `u16` indices.
+    ///
+    /// The buffer content is the native-endian byte representation of
+    /// `data`, so its size is `data.len() * size_of::<T>()`.
+    ///
+    /// [`Buffer`]: struct.Buffer.html
+    pub fn make_buffer_immutable<T: Pod>(
+        &mut self,
+        buffer_type: BufferType,
+        data: &[T],
+    ) -> Option<Buffer> {
+        let content = slice_as_bytes(data).to_vec();
+        let desc = BufferDesc {
+            size: content.len(),
+            buffer_type,
+            usage: Usage::Immutable,
+            content,
+            #[cfg(feature = "gl")]
+            gl_buffers: [0; NUM_INFLIGHT_FRAMES],
+            #[cfg(feature = "metal")]
+            metal_buffers: [ptr::null(); NUM_INFLIGHT_FRAMES],
+            #[cfg(feature = "d3d11")]
+            d3d11_buffers: ptr::null(),
+        };
+        Buffer::make(self, desc)
+    }
+
+    /// Create a [`Buffer`] wrapping an existing GL buffer object.
+    ///
+    /// The buffer is used for every inflight frame, so `desc.usage`
+    /// should be `Usage::Immutable`. Grafiska does not take ownership of
+    /// the buffer: destroying it leaves the GL buffer alive.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    ///
+    /// [`Buffer`]: struct.Buffer.html
+    #[cfg(feature = "gl")]
+    pub fn make_buffer_from_gl_buffer(
+        &mut self,
+        gl_name: u32,
+        mut desc: BufferDesc,
+    ) -> Option<Buffer> {
+        desc.gl_buffers = [gl_name; NUM_INFLIGHT_FRAMES];
+        Buffer::make(self, desc)
+    }
+
+    /// Create an [`Image`] wrapping an existing GL texture object.
+    ///
+    /// The texture is used for every inflight frame, so `desc.usage`
+    /// should be `Usage::Immutable`. Grafiska does not take ownership of
+    /// the texture: destroying the image leaves the GL texture alive.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    ///
+    /// [`Image`]: struct.Image.html
+    #[cfg(feature = "gl")]
+    pub fn make_image_from_gl_texture(
+        &mut self,
+        gl_name: u32,
+        mut desc: ImageDesc,
+    ) -> Option<Image> {
+        desc.gl_textures = [gl_name; NUM_INFLIGHT_FRAMES];
+        Image::make(self, desc)
+    }
+
     /// Update the content of a buffer resource.
     ///
     /// The resource must have been created with `USAGE_DYNAMIC` or
-    /// `USAGE_STREAM`.
-    pub fn update_buffer(&mut self, buf: Buffer, data_ptr: *const os::raw::c_void, data_size: u32) {
-        unimplemented!();
+    /// `USAGE_STREAM`. Updates of immutable buffers are ignored.
+    pub fn update_buffer(&mut self, buf: Buffer, data: &[u8]) {
+        let frame_index = self.frame_index;
+        if let Some(res) = self.buffer_pool.lookup_checked_mut(&buf) {
+            if res.usage == Usage::Immutable {
+                warn!("update_buffer: can't update immutable buffer {:?}", buf);
+            } else if data.len() > res.size {
+                warn!(
+                    "update_buffer: {} bytes don't fit into buffer {:?} of {} bytes",
+                    data.len(),
+                    buf,
+                    res.size
+                );
+            } else {
+                self.backend.update_buffer(res, frame_index, data);
+                self.frame_stats.num_buffer_updates += 1;
+            }
+        }
+    }
+
+    /// Append data to a buffer resource, returning the byte offset at which
+    /// it was written, such as to pass to `draw()` as vertex or index
+    /// offset.
+    ///
+    /// The resource must have been created with `USAGE_DYNAMIC` or
+    /// `USAGE_STREAM`. Each frame, the first append starts at offset 0, and
+    /// subsequent appends follow the previous ones, aligned to 4 bytes. A
+    /// buffer can't be both appended to and updated in the same frame.
+    /// Returns `None` if the data doesn't fit into the rest of the buffer.
+    pub fn append_buffer(&mut self, buf: Buffer, data: &[u8]) -> Option<u32> {
+        let frame_index = self.frame_index;
+        let res = self.buffer_pool.lookup_checked_mut(&buf)?;
+        if res.usage == Usage::Immutable {
+            warn!("append_buffer: can't append to immutable buffer {:?}", buf);
+            return None;
+        }
+        let offset = self.backend.append_buffer(res, frame_index, data)?;
+        self.frame_stats.num_buffer_updates += 1;
+        Some(offset as u32)
+    }
+
+    /// Read back the content of a buffer resource, starting at `offset`.
+    ///
+    /// The buffer must have been created with a usage that allows the
+    /// CPU to read its content, which excludes `Usage::Immutable`. The
+    /// range `offset..offset + out.len()` must lie within the buffer.
+    ///
+    /// Fails with `ResourceError::InvalidHandle` if the buffer isn't live
+    /// or failed to initialize.
+    pub fn get_buffer_data(
+        &mut self,
+        buf: Buffer,
+        offset: usize,
+        out: &mut [u8],
+    ) -> Result<(), ResourceError> {
+        let res = self
+            .buffer_pool
+            .lookup(&buf)
+            .filter(|res| res.slot.state == ResourceState::Valid)
+            .ok_or(ResourceError::InvalidHandle)?;
+        match offset.checked_add(out.len()) {
+            Some(end) if end <= res.size => {}
+            _ => return Err(ResourceError::OutOfBounds),
+        }
+        self.backend.get_buffer_data(res, offset, out)
     }
 
     /// Update the content of an image resource.
     ///
     /// The resource must have been created with `USAGE_DYNAMIC` or
-    /// `USAGE_STREAM`.
-    pub fn update_image(&mut self, img: Image, data: ImageContent) {
-        unimplemented!();
+    /// `USAGE_STREAM`. Updates of immutable images are ignored.
+    pub fn update_image(&mut self, img: Image, data: &ImageContent) {
+        let frame_index = self.frame_index;
+        if let Some(res) = self.image_pool.lookup_checked_mut(&img) {
+            if res.usage == Usage::Immutable {
+                warn!("update_image: can't update immutable image {:?}", img);
+            } else {
+                self.backend.update_image(res, frame_index, data);
+                self.frame_stats.num_image_updates += 1;
+            }
+        }
+    }
+
+    /// Generate the mipmap levels of an image from its top level.
+    ///
+    /// The image must have been created with more than one mipmap level
+    /// and a mipmap `min_filter`. Compressed pixel formats can't be
+    /// generated by the backend, so this is a no-op for them.
+    ///
+    /// Fails with `ResourceError::Unsupported` on backends that can't
+    /// generate mipmaps.
+    pub fn generate_mipmaps(&mut self, img: Image) -> Result<(), ResourceError> {
+        let res = self
+            .image_pool
+            .lookup(&img)
+            .ok_or(ResourceError::InvalidHandle)?;
+        if res.num_mipmaps <= 1 || !res.min_filter.is_mipmap_filter() {
+            return Err(ResourceError::NotMipmapped);
+        }
+        if res.pixel_format.is_compressed_pixel_format() {
+            warn!(
+                "generate_mipmaps: can't generate mipmaps for compressed format {:?}",
+                res.pixel_format
+            );
+            return Ok(());
+        }
+        self.backend.generate_mipmaps(res)
+    }
+
+    /// Whether a rendering pass was begun and not yet ended.
+    pub fn is_in_pass(&self) -> bool {
+        self.in_pass
+    }
+
+    /// The offscreen pass that is currently being rendered.
+    ///
+    /// This is `None` outside of a pass and within a default pass.
+    pub fn query_current_pass(&self) -> Option<Pass> {
+        self.current_pass
+    }
+
+    /// The pipeline of the last draw state that was applied in the current
+    /// pass, or `None` if there is none.
+    pub fn query_current_pipeline(&self) -> Option<Pipeline> {
+        self.current_pipeline
+    }
+
+    /// Whether rendering calls can be issued, i.e. there is a current pass
+    /// and it is valid.
+    ///
+    /// Calls outside a pass are dropped, with a warning when validation is
+    /// enabled. Calls within an invalid pass are dropped silently.
+    fn check_in_pass(&self, func: &str) -> bool {
+        if !self.in_pass {
+            if self.validation {
+                warn!("{}: called outside of a pass", func);
+            }
+            return false;
+        }
+        self.pass_valid
     }
 
     /// Start rendering to the default framebuffer.
+    ///
+    /// In headless mode, there is no default framebuffer, so the rendering
+    /// calls of the pass are dropped.
     pub fn begin_default_pass(&mut self, pass_action: &PassAction, width: u32, height: u32) {
-        unimplemented!();
+        self.trace(TraceEvent::BeginDefaultPass { width, height });
+        self.current_pass = None;
+        self.in_pass = true;
+        if self.headless {
+            warn!("begin_default_pass: no default framebuffer in headless mode");
+            self.pass_valid = false;
+            return;
+        }
+        self.pass_valid = true;
+        self.backend.begin_pass(None, pass_action, width, height);
+    }
+
+    /// Clear the default framebuffer to `color`, and its depth and stencil
+    /// buffers to `1.0` and `0`.
+    ///
+    /// This begins and ends a default pass, but doesn't `commit()` the
+    /// frame, which is left to the caller.
+    pub fn clear_default(&mut self, color: [f32; 4], width: u32, height: u32) {
+        let action = PassAction {
+            colors: [ColorAttachmentAction {
+                action: Action::Clear,
+                val: color,
+            }; MAX_COLOR_ATTACHMENTS],
+            depth: DepthAttachmentAction {
+                action: Action::Clear,
+                val: 1.0,
+            },
+            stencil: StencilAttachmentAction {
+                action: Action::Clear,
+                val: 0,
+            },
+        };
+        self.begin_default_pass(&action, width, height);
+        self.end_pass();
     }
 
     /// Start rendering to an offscreen framebuffer.
+    ///
+    /// If the pass isn't valid, all rendering calls until the matching
+    /// `end_pass()` are silently dropped.
     pub fn begin_pass(&mut self, pass: Pass, pass_action: &PassAction) {
-        unimplemented!();
+        self.trace(TraceEvent::BeginPass(pass));
+        self.current_pass = Some(pass);
+        self.in_pass = true;
+        match self.pass_pool.lookup_checked(&pass) {
+            Some(res) if res.slot.state == ResourceState::Valid => {
+                self.pass_valid = true;
+                let (width, height) = res.size();
+                self.backend
+                    .begin_pass(Some(res), pass_action, width as u32, height as u32);
+            }
+            _ => self.pass_valid = false,
+        }
     }
 
     /// Set a new viewport rectangle.
@@ -1569,15 +4176,16 @@ impl Context {
         height: u32,
         origin_top_left: bool,
     ) {
-        if self.pass_valid {
-            self.backend
-                .apply_viewport(x, y, width, height, origin_top_left);
+        if self.check_in_pass("apply_viewport") {
+            let flip = self.flip_viewport_y(origin_top_left);
+            self.backend.apply_viewport(x, y, width, height, flip);
         }
     }
 
     /// Set a new scissor rectangle.
     ///
-    /// This must be called from within a rendering pass.
+    /// This must be called from within a rendering pass. Rectangles that
+    /// reach past the edges of the pass are clamped to it.
     pub fn apply_scissor_rect(
         &mut self,
         x: u32,
@@ -1586,10 +4194,65 @@ impl Context {
         height: u32,
         origin_top_left: bool,
     ) {
-        if self.pass_valid {
-            self.backend
-                .apply_scissor_rect(x, y, width, height, origin_top_left);
+        if self.check_in_pass("apply_scissor_rect") {
+            let flip = self.flip_viewport_y(origin_top_left);
+            self.backend.apply_scissor_rect(x, y, width, height, flip);
+        }
+    }
+
+    /// Reset the scissor rectangle to cover the whole framebuffer of the
+    /// current pass, so that it doesn't clip subsequent draws.
+    ///
+    /// This must be called from within a rendering pass.
+    pub fn disable_scissor(&mut self) {
+        if self.check_in_pass("disable_scissor") {
+            self.backend.disable_scissor();
+        }
+    }
+
+    /// Read back pixels from the framebuffer of the current rendering pass.
+    ///
+    /// This must be called from within a rendering pass.
+    ///
+    /// The rectangle is given with a top-left origin and the rows are
+    /// written into `out` from top to bottom, regardless of the origin
+    /// used by the rendering backend. The length of `out` must be exactly
+    /// `width * height * format.bytesize()`.
+    ///
+    /// This is useful for screenshots and golden-image tests.
+    ///
+    /// Fails with `ResourceError::OutOfBounds` if `out` has the wrong
+    /// length or the rectangle doesn't fit in the pass framebuffer, and
+    /// with `ResourceError::Unsupported` when called outside a valid pass
+    /// or for a format the backend can't read back (compressed, depth and
+    /// `None` formats are never readable).
+    pub fn read_pixels(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        out: &mut [u8],
+    ) -> Result<(), ResourceError> {
+        let bytesize = match format.checked_bytesize() {
+            Some(bytesize) => bytesize,
+            None => return Err(ResourceError::Unsupported),
+        };
+        let row_pitch = (width as usize)
+            .checked_mul(bytesize)
+            .ok_or(ResourceError::OutOfBounds)?;
+        if row_pitch.checked_mul(height as usize) != Some(out.len()) {
+            return Err(ResourceError::OutOfBounds);
         }
+        if !self.check_in_pass("read_pixels") {
+            return Err(ResourceError::Unsupported);
+        }
+        self.backend.read_pixels(x, y, width, height, format, out)?;
+        if self.flip_viewport_y(true) {
+            flip_rows(out, row_pitch);
+        }
+        Ok(())
     }
 
     /// Update the resource bindings for the next draw call.
@@ -1601,7 +4264,118 @@ impl Context {
     ///
     /// [`DrawState`]: struct.DrawState.html
     pub fn apply_draw_state(&mut self, ds: DrawState) {
-        unimplemented!();
+        self.trace(TraceEvent::ApplyDrawState(ds.pipeline));
+        self.next_draw_valid = false;
+        if !self.check_in_pass("apply_draw_state") {
+            return;
+        }
+        if self.validation {
+            // Handles are plain IDs, so a draw state can outlive the
+            // resources it refers to.
+            if let Some(field) = self.find_stale_handle(&ds) {
+                warn!("apply_draw_state: {} refers to a destroyed resource", field);
+                return;
+            }
+        }
+        let pip = match self.pipeline_pool.lookup_checked(&ds.pipeline) {
+            Some(pip) if pip.slot.state == ResourceState::Valid => pip,
+            // Pipelines that are allocated but not initialized yet are
+            // skipped silently.
+            None if self.pipeline_pool.contains(&ds.pipeline) => return,
+            _ => {
+                if self.validation {
+                    warn!("apply_draw_state: pipeline {:?} is not valid", ds.pipeline);
+                }
+                return;
+            }
+        };
+        if self.validation && !self.validate_draw_state(pip, &ds) {
+            return;
+        }
+
+        let buffer_pool = &self.buffer_pool;
+        let image_pool = &self.image_pool;
+        let lookup_buffer = |buf: &Buffer| {
+            buffer_pool
+                .lookup_checked(buf)
+                .filter(|b| b.slot.state == ResourceState::Valid)
+        };
+        let lookup_images = |imgs: &[Image]| {
+            imgs.iter()
+                .filter(|img| img.id != INVALID_ID)
+                .map(|img| {
+                    image_pool
+                        .lookup_checked(img)
+                        .filter(|i| i.slot.state == ResourceState::Valid)
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+
+        let vbs = match ds
+            .vertex_buffers
+            .iter()
+            .filter(|buf| buf.id != INVALID_ID)
+            .map(&lookup_buffer)
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(vbs) => vbs,
+            None => return,
+        };
+        let ib = match ds.index_buffer {
+            Some(ref buf) => match lookup_buffer(buf) {
+                Some(ib) => Some(ib),
+                None => return,
+            },
+            None => None,
+        };
+        let vs_imgs = match lookup_images(&ds.vs_images) {
+            Some(vs_imgs) => vs_imgs,
+            None => return,
+        };
+        let fs_imgs = match lookup_images(&ds.fs_images) {
+            Some(fs_imgs) => fs_imgs,
+            None => return,
+        };
+
+        self.frame_stats.num_apply_draw_state += 1;
+        if self.current_pipeline == Some(ds.pipeline) {
+            self.frame_stats.num_redundant_state_skipped += 1;
+        }
+        self.current_pipeline = Some(ds.pipeline);
+        self.next_draw_valid = true;
+        self.backend
+            .apply_draw_state(pip, &vbs, ib, &vs_imgs, &fs_imgs);
+    }
+
+    /// Override the constant blend color of the current pipeline for
+    /// subsequent draws.
+    ///
+    /// This must be called from within a rendering pass. The override lasts
+    /// until the next `apply_draw_state()`, which restores the
+    /// `blend_color` of its pipeline's `BlendState`, even if it applies the
+    /// same pipeline again.
+    ///
+    /// This has no effect on the Metal backend yet.
+    pub fn apply_blend_color(&mut self, color: [f32; 4]) {
+        if self.check_in_pass("apply_blend_color") {
+            self.backend.apply_blend_color(color);
+        }
+    }
+
+    /// Override the stencil reference value of the current pipeline for
+    /// subsequent draws.
+    ///
+    /// The stencil compare functions and masks of the pipeline are kept.
+    /// This must be called from within a rendering pass. The override lasts
+    /// until the next `apply_draw_state()`, which restores the
+    /// `stencil_ref` of its pipeline's `DepthStencilState`, even if it
+    /// applies the same pipeline again.
+    ///
+    /// This has no effect on the Metal backend yet.
+    pub fn apply_stencil_ref(&mut self, stencil_ref: u8) {
+        if self.check_in_pass("apply_stencil_ref") {
+            self.backend.apply_stencil_ref(stencil_ref);
+        }
     }
 
     /// Update shader uniform data.
@@ -1614,20 +4388,67 @@ impl Context {
     ) {
         assert!(ub_index < MAX_SHADERSTAGE_UBS as u32);
         assert!(!data.is_null() && (num_bytes > 0));
-        if self.pass_valid && self.next_draw_valid {
+        if self.check_in_pass("apply_uniform_block") && self.next_draw_valid {
             self.backend
                 .apply_uniform_block(stage, ub_index, data, num_bytes);
         }
     }
 
+    /// Update shader uniform data from a typed uniform block.
+    ///
+    /// This is a safe wrapper around [`apply_uniform_block()`].
+    ///
+    /// [`apply_uniform_block()`]: #method.apply_uniform_block
+    pub fn apply_uniforms<U: Uniforms>(&mut self, stage: ShaderStage, ub_index: u32, uniforms: &U) {
+        let bytes = uniforms.as_bytes();
+        debug_assert_eq!(bytes.len(), U::block_size() as usize);
+        self.apply_uniform_block(
+            stage,
+            ub_index,
+            bytes.as_ptr() as *const os::raw::c_void,
+            bytes.len() as u32,
+        );
+    }
+
     /// Kick off a draw call.
     ///
     /// This uses the resource bindings that were supplied to `apply_draw_state()`
     /// as well as uniform blocks supplied via `apply_uniform_block()`.
+    ///
+    /// Nothing is drawn if `num_instances` is 0.
     pub fn draw(&mut self, base_element: u32, num_elements: u32, num_instances: u32) {
-        if self.pass_valid && self.next_draw_valid {
+        self.trace(TraceEvent::Draw {
+            base_element,
+            num_elements,
+            num_instances,
+        });
+        if num_instances == 0 {
+            return;
+        }
+        if self.check_in_pass("draw") && self.next_draw_valid {
             self.backend.draw(base_element, num_elements, num_instances);
+            self.frame_stats.num_draws += 1;
+            self.frame_stats.num_instances += num_instances;
+        }
+    }
+
+    /// Draw a single triangle covering the whole viewport.
+    ///
+    /// This issues a non-indexed draw of 3 vertices with the current
+    /// pipeline, which must generate the vertex positions from the vertex
+    /// index, e.g. `gl_VertexID`, instead of reading vertex buffers.
+    pub fn draw_fullscreen(&mut self) {
+        if let Some(pip) = self.current_pipeline {
+            let uses_buffers = self.pipeline_pool.lookup(&pip).is_some_and(|res| {
+                res.index_type().is_some()
+                    || (0..MAX_SHADERSTAGE_BUFFERS).any(|i| res.uses_vertex_buffer(i))
+            });
+            if uses_buffers {
+                warn!("draw_fullscreen: the current pipeline reads vertex or index buffers");
+                return;
+            }
         }
+        self.draw(0, 3, 1);
     }
 
     /// Finish the current rendering pass.
@@ -1635,18 +4456,26 @@ impl Context {
     /// If the render target is an MSAA render target, then an MSAA resolve will
     /// occur here.
     pub fn end_pass(&mut self) {
-        if self.pass_valid {
-            self.backend.end_pass();
-            self.current_pass = None;
-            self.current_pipeline = None;
-            self.pass_valid = false;
+        self.trace(TraceEvent::EndPass);
+        let pass_valid = self.check_in_pass("end_pass");
+        self.in_pass = false;
+        if pass_valid {
+            let pass_pool = &self.pass_pool;
+            let pass = self.current_pass.and_then(|pass| pass_pool.lookup(&pass));
+            self.backend.end_pass(pass);
         }
+        self.current_pass = None;
+        self.current_pipeline = None;
+        self.pass_valid = false;
     }
 
     /// Finish rendering the current frame.
     pub fn commit(&mut self) {
+        self.trace(TraceEvent::Commit);
         self.backend.commit();
         self.frame_index += 1;
+        self.last_frame_stats = self.frame_stats;
+        self.frame_stats = FrameStats::default();
     }
 
     /// Helper function for creating a `VertexAttrDesc` with a name.
@@ -1686,9 +4515,776 @@ impl Context {
     }
 }
 
+/// Reverse the order of the rows of `pitch` bytes in `data`.
+fn flip_rows(data: &mut [u8], pitch: usize) {
+    if pitch == 0 {
+        return;
+    }
+    let num_rows = data.len() / pitch;
+    for row in 0..num_rows / 2 {
+        let (top, bottom) = data.split_at_mut((num_rows - row - 1) * pitch);
+        top[row * pitch..(row + 1) * pitch].swap_with_slice(&mut bottom[..pitch]);
+    }
+}
+
+/// Convert the `y` coordinate of a rectangle with the given `height` between
+/// a top-left and a bottom-left origin within a pass of `pass_height`.
+///
+/// Rectangles that reach past the edge of the pass saturate to `0` instead
+/// of wrapping around; clamp them with `clamp_rect()` first to keep the
+/// result exact.
+fn flip_y(pass_height: u32, y: u32, height: u32) -> u32 {
+    pass_height.saturating_sub(y.saturating_add(height))
+}
+
+/// Clamp a rectangle so that it lies within a pass of the given dimensions.
+fn clamp_rect(
+    pass_width: u32,
+    pass_height: u32,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> (u32, u32, u32, u32) {
+    let x = x.min(pass_width);
+    let y = y.min(pass_height);
+    let width = width.min(pass_width - x);
+    let height = height.min(pass_height - y);
+    (x, y, width, height)
+}
+
+/// A scope in which OpenGL is called directly, bypassing Grafiska.
+///
+/// This is returned by [`Context::external_gl_scope()`] and resets the
+/// state cache of the context when it is dropped.
+///
+/// This is only present when the `gl` feature is enabled.
+///
+/// [`Context::external_gl_scope()`]: struct.Context.html#method.external_gl_scope
+#[cfg(feature = "gl")]
+pub struct ExternalGlGuard<'a> {
+    ctx: &'a mut Context,
+}
+
+#[cfg(feature = "gl")]
+impl<'a> Drop for ExternalGlGuard<'a> {
+    fn drop(&mut self) {
+        self.ctx.reset_state_cache();
+    }
+}
+
 impl Drop for Context {
     /// Shutdown the Grafiska library at the end of your program.
     fn drop(&mut self) {
         self.shutdown()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_builder_defaults_match_config_default() {
+        let built = Config::builder().build().unwrap();
+        let default = Config::default();
+        assert_eq!(built.buffer_pool_size, default.buffer_pool_size);
+        assert_eq!(built.image_pool_size, default.image_pool_size);
+        assert_eq!(built.shader_pool_size, default.shader_pool_size);
+        assert_eq!(built.pipeline_pool_size, default.pipeline_pool_size);
+        assert_eq!(built.pass_pool_size, default.pass_pool_size);
+        assert!(built.trace_hook.is_none());
+        assert_eq!(built.validation, default.validation);
+        assert_eq!(built.debug_pool_lifo, default.debug_pool_lifo);
+        assert_eq!(
+            built.panic_on_invalid_handle,
+            default.panic_on_invalid_handle
+        );
+        assert_eq!(built.headless, default.headless);
+    }
+
+    #[test]
+    fn config_builder_overrides_defaults() {
+        let config = Config::builder()
+            .buffer_pool_size(1)
+            .image_pool_size(2)
+            .shader_pool_size(3)
+            .pipeline_pool_size(4)
+            .pass_pool_size(5)
+            .validation(!cfg!(debug_assertions))
+            .debug_pool_lifo(true)
+            .panic_on_invalid_handle(true)
+            .headless(true)
+            .build()
+            .unwrap();
+        assert_eq!(config.buffer_pool_size, 1);
+        assert_eq!(config.image_pool_size, 2);
+        assert_eq!(config.shader_pool_size, 3);
+        assert_eq!(config.pipeline_pool_size, 4);
+        assert_eq!(config.pass_pool_size, 5);
+        assert_eq!(config.validation, !cfg!(debug_assertions));
+        assert!(config.debug_pool_lifo);
+        assert!(config.panic_on_invalid_handle);
+        assert!(config.headless);
+    }
+
+    #[cfg(feature = "gl")]
+    #[test]
+    fn config_builder_overrides_gl_options() {
+        let config = Config::builder()
+            .gl_force_gles2(true)
+            .gl_use_sampler_objects(true)
+            .gl_seamless_cubemaps(true)
+            .build()
+            .unwrap();
+        assert!(config.gl_force_gles2);
+        assert!(config.gl_use_sampler_objects);
+        assert!(config.gl_seamless_cubemaps);
+        let default = Config::default();
+        assert!(!default.gl_force_gles2);
+        assert!(!default.gl_use_sampler_objects);
+        assert!(!default.gl_seamless_cubemaps);
+    }
+
+    #[test]
+    fn config_builder_rejects_invalid_pool_sizes() {
+        let err = Config::builder().image_pool_size(0).build().unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::InvalidPoolSize {
+                pool: "image",
+                size: 0
+            }
+        );
+        let err = Config::builder()
+            .pass_pool_size(pool::MAX_POOL_SIZE)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::InvalidPoolSize {
+                pool: "pass",
+                size: pool::MAX_POOL_SIZE
+            }
+        );
+        assert!(Config::builder()
+            .pass_pool_size(pool::MAX_POOL_SIZE - 1)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn clamp_rect_keeps_rects_inside_the_pass() {
+        assert_eq!(clamp_rect(640, 480, 10, 20, 100, 50), (10, 20, 100, 50));
+        assert_eq!(clamp_rect(640, 480, 0, 0, 640, 480), (0, 0, 640, 480));
+    }
+
+    #[test]
+    fn clamp_rect_clips_rects_off_the_right_and_bottom_edges() {
+        assert_eq!(clamp_rect(640, 480, 600, 20, 100, 50), (600, 20, 40, 50));
+        assert_eq!(clamp_rect(640, 480, 10, 450, 100, 50), (10, 450, 100, 30));
+        assert_eq!(clamp_rect(640, 480, 600, 450, 100, 50), (600, 450, 40, 30));
+    }
+
+    #[test]
+    fn clamp_rect_clips_rects_larger_than_the_pass() {
+        // The coordinates are unsigned, so a rect can only leave the pass on
+        // the left or top edge by being larger than the pass.
+        assert_eq!(clamp_rect(640, 480, 0, 0, 1000, 1000), (0, 0, 640, 480));
+        assert_eq!(
+            clamp_rect(640, 480, 0, 0, u32::MAX, u32::MAX),
+            (0, 0, 640, 480)
+        );
+    }
+
+    #[test]
+    fn clamp_rect_collapses_rects_fully_outside_the_pass() {
+        assert_eq!(clamp_rect(640, 480, 700, 20, 100, 50), (640, 20, 0, 50));
+        assert_eq!(clamp_rect(640, 480, 10, 500, 100, 50), (10, 480, 100, 0));
+        assert_eq!(
+            clamp_rect(640, 480, u32::MAX, u32::MAX, 1, 1),
+            (640, 480, 0, 0)
+        );
+    }
+
+    #[test]
+    fn clamp_rect_handles_an_empty_pass() {
+        assert_eq!(clamp_rect(0, 0, 10, 20, 100, 50), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn flip_rows_reverses_the_row_order() {
+        let mut data = [1, 1, 2, 2, 3, 3];
+        flip_rows(&mut data, 2);
+        assert_eq!(data, [3, 3, 2, 2, 1, 1]);
+        let mut data = [1, 1, 2, 2, 3, 3, 4, 4];
+        flip_rows(&mut data, 2);
+        assert_eq!(data, [4, 4, 3, 3, 2, 2, 1, 1]);
+    }
+
+    #[test]
+    fn flip_rows_ignores_empty_rows() {
+        let mut data: [u8; 0] = [];
+        flip_rows(&mut data, 0);
+        flip_rows(&mut data, 4);
+    }
+
+    #[test]
+    fn pixel_format_bytesize() {
+        assert_eq!(PixelFormat::RGBA32F.bytesize(), 16);
+        assert_eq!(PixelFormat::RGBA16F.bytesize(), 8);
+        assert_eq!(PixelFormat::RGBA8.bytesize(), 4);
+        assert_eq!(PixelFormat::R11G11B10F.bytesize(), 4);
+        assert_eq!(PixelFormat::RGB8.bytesize(), 3);
+        assert_eq!(PixelFormat::R5G6B5.bytesize(), 2);
+        assert_eq!(PixelFormat::RG8.bytesize(), 2);
+        assert_eq!(PixelFormat::R8.bytesize(), 1);
+    }
+
+    #[test]
+    fn pixel_format_checked_bytesize_rejects_formats_without_a_pixel_size() {
+        assert_eq!(PixelFormat::RGBA8.checked_bytesize(), Some(4));
+        assert_eq!(PixelFormat::None.checked_bytesize(), None);
+        assert_eq!(PixelFormat::Depth.checked_bytesize(), None);
+        assert_eq!(PixelFormat::DepthStencil.checked_bytesize(), None);
+        assert_eq!(PixelFormat::DXT1.checked_bytesize(), None);
+        assert_eq!(PixelFormat::ETC2_RGB8.checked_bytesize(), None);
+    }
+
+    #[test]
+    fn pixel_format_pitch_of_uncompressed_formats() {
+        assert_eq!(PixelFormat::RGBA8.row_pitch(10), 40);
+        assert_eq!(PixelFormat::RGB8.row_pitch(3), 9);
+        assert_eq!(PixelFormat::RGBA8.surface_pitch(10, 5), 200);
+        assert_eq!(PixelFormat::R8.surface_pitch(7, 3), 21);
+    }
+
+    #[test]
+    fn pixel_format_pitch_of_compressed_formats() {
+        // DXT1 and ETC2 store 4x4 blocks in 8 bytes, DXT3 and DXT5 in 16.
+        assert_eq!(PixelFormat::DXT1.row_pitch(16), 32);
+        assert_eq!(PixelFormat::DXT1.row_pitch(1), 8);
+        assert_eq!(PixelFormat::DXT5.row_pitch(16), 64);
+        assert_eq!(PixelFormat::DXT5.row_pitch(5), 32);
+        assert_eq!(PixelFormat::DXT1.surface_pitch(16, 16), 128);
+        assert_eq!(PixelFormat::DXT1.surface_pitch(16, 1), 32);
+        assert_eq!(PixelFormat::ETC2_RGB8.surface_pitch(8, 8), 32);
+        // PVRTC textures are at least 2 blocks wide.
+        assert_eq!(PixelFormat::PVRTC4_RGBA.row_pitch(4), 16);
+        assert_eq!(PixelFormat::PVRTC4_RGBA.row_pitch(16), 32);
+        assert_eq!(PixelFormat::PVRTC2_RGBA.row_pitch(16), 32);
+    }
+
+    #[test]
+    fn flip_y_converts_between_top_and_bottom_origins() {
+        assert_eq!(flip_y(480, 0, 480), 0);
+        assert_eq!(flip_y(480, 0, 100), 380);
+        assert_eq!(flip_y(480, 380, 100), 0);
+        assert_eq!(flip_y(480, 10, 20), 450);
+        // Flipping twice gives back the original coordinate.
+        assert_eq!(flip_y(480, flip_y(480, 10, 20), 20), 10);
+    }
+
+    #[test]
+    fn flip_y_saturates_rects_past_the_pass() {
+        assert_eq!(flip_y(480, 400, 100), 0);
+        assert_eq!(flip_y(480, u32::MAX, 1), 0);
+        assert_eq!(flip_y(480, 0, u32::MAX), 0);
+    }
+
+    #[test]
+    fn pass_action_clear_uses_default_depth_and_stencil() {
+        let action = PassAction::clear([0.25, 0.5, 0.75, 1.0]);
+        for color in action.colors.iter() {
+            assert_eq!(color.action, Action::Clear);
+            assert_eq!(color.val, [0.25, 0.5, 0.75, 1.0]);
+        }
+        assert_eq!(action.depth.action, Action::Clear);
+        assert_eq!(action.depth.val, 1.0);
+        assert_eq!(action.stencil.action, Action::Clear);
+        assert_eq!(action.stencil.val, 0);
+    }
+
+    #[test]
+    fn pass_action_clear_full_sets_every_attachment() {
+        let action = PassAction::clear_full([1.0, 0.0, 0.0, 1.0], 0.5, 0x80);
+        assert_eq!(action.colors.len(), MAX_COLOR_ATTACHMENTS);
+        for color in action.colors.iter() {
+            assert_eq!(color.action, Action::Clear);
+            assert_eq!(color.val, [1.0, 0.0, 0.0, 1.0]);
+        }
+        assert_eq!(action.depth.action, Action::Clear);
+        assert_eq!(action.depth.val, 0.5);
+        assert_eq!(action.stencil.action, Action::Clear);
+        assert_eq!(action.stencil.val, 0x80);
+    }
+
+    fn render_target(
+        pixel_format: PixelFormat,
+        width: usize,
+        height: usize,
+        sample_count: usize,
+    ) -> backend::ImageResource {
+        let mut img = backend::ImageResource::default();
+        img.slot.state = ResourceState::Valid;
+        img.image_type = ImageType::Texture2D;
+        img.render_target = true;
+        img.width = width;
+        img.height = height;
+        img.depth = 1;
+        img.num_mipmaps = 1;
+        img.pixel_format = pixel_format;
+        img.sample_count = sample_count;
+        img
+    }
+
+    /// Images 1 and 2 are 256x256 color targets, 3 is a matching
+    /// depth-stencil target, and 0 was never initialized.
+    fn pass_images() -> Vec<backend::ImageResource> {
+        vec![
+            backend::ImageResource::default(),
+            render_target(PixelFormat::RGBA8, 256, 256, 1),
+            render_target(PixelFormat::RGBA8, 256, 256, 1),
+            render_target(PixelFormat::DepthStencil, 256, 256, 1),
+        ]
+    }
+
+    fn pass_desc(colors: &[u32], depth_stencil: Option<u32>) -> PassDesc {
+        let mut desc = PassDesc {
+            color_attachments: Default::default(),
+            depth_stencil_attachment: None,
+        };
+        for (att, &id) in desc.color_attachments.iter_mut().zip(colors) {
+            att.image = Image::with(id);
+        }
+        desc.depth_stencil_attachment = depth_stencil.map(|id| AttachmentDesc {
+            image: Image::with(id),
+            ..Default::default()
+        });
+        desc
+    }
+
+    fn validate(
+        desc: &PassDesc,
+        images: &[backend::ImageResource],
+        features: &[Feature],
+    ) -> Result<(), PassValidationError> {
+        validate_pass_attachments(
+            desc,
+            |image| images.get(image.id() as usize),
+            |feature| features.contains(&feature),
+        )
+    }
+
+    const ALL_PASS_FEATURES: &[Feature] =
+        &[Feature::MultipleRenderTarget, Feature::MSAARenderTargets];
+
+    #[test]
+    fn validate_pass_accepts_matching_attachments() {
+        let images = pass_images();
+        let desc = pass_desc(&[1], Some(3));
+        assert_eq!(validate(&desc, &images, &[]), Ok(()));
+        let desc = pass_desc(&[1, 2], Some(3));
+        assert_eq!(validate(&desc, &images, ALL_PASS_FEATURES), Ok(()));
+        let desc = pass_desc(&[], Some(3));
+        assert_eq!(validate(&desc, &images, &[]), Ok(()));
+    }
+
+    #[test]
+    fn validate_pass_rejects_empty_and_gapped_attachments() {
+        let images = pass_images();
+        assert_eq!(
+            validate(&pass_desc(&[], None), &images, &[]),
+            Err(PassValidationError::NoColorAttachments)
+        );
+        assert_eq!(
+            validate(&pass_desc(&[1, 0, 2], None), &images, ALL_PASS_FEATURES),
+            Err(PassValidationError::AttachmentGap(PassAttachment::Color(2)))
+        );
+        assert_eq!(
+            validate(&pass_desc(&[1, 2], None), &images, &[]),
+            Err(PassValidationError::MRTNotSupported)
+        );
+    }
+
+    #[test]
+    fn validate_pass_rejects_invalid_images() {
+        let images = pass_images();
+        assert_eq!(
+            validate(&pass_desc(&[9], None), &images, &[]),
+            Err(PassValidationError::InvalidImage(PassAttachment::Color(0)))
+        );
+        assert_eq!(
+            validate(&pass_desc(&[1], Some(9)), &images, &[]),
+            Err(PassValidationError::InvalidImage(
+                PassAttachment::DepthStencil
+            ))
+        );
+
+        let mut images = pass_images();
+        let color = PassAttachment::Color(0);
+        images[2].render_target = false;
+        assert_eq!(
+            validate(&pass_desc(&[2], None), &images, &[]),
+            Err(PassValidationError::NotRenderTarget(color))
+        );
+    }
+
+    #[test]
+    fn validate_pass_rejects_mismatched_attachments() {
+        let mut images = pass_images();
+        images[2].width = 128;
+        images[3].height = 128;
+        assert_eq!(
+            validate(&pass_desc(&[1, 2], None), &images, ALL_PASS_FEATURES),
+            Err(PassValidationError::SizeMismatch(PassAttachment::Color(1)))
+        );
+        assert_eq!(
+            validate(&pass_desc(&[1], Some(3)), &images, &[]),
+            Err(PassValidationError::SizeMismatch(
+                PassAttachment::DepthStencil
+            ))
+        );
+
+        let mut images = pass_images();
+        images[2].pixel_format = PixelFormat::RGBA16F;
+        assert_eq!(
+            validate(&pass_desc(&[1, 2], None), &images, ALL_PASS_FEATURES),
+            Err(PassValidationError::ColorFormatMismatch(
+                PassAttachment::Color(1)
+            ))
+        );
+
+        let mut images = pass_images();
+        images[3].sample_count = 4;
+        assert_eq!(
+            validate(&pass_desc(&[1], Some(3)), &images, ALL_PASS_FEATURES),
+            Err(PassValidationError::SampleCountMismatch(
+                PassAttachment::DepthStencil
+            ))
+        );
+    }
+
+    #[test]
+    fn validate_pass_checks_msaa_support_and_resolve() {
+        let mut images = pass_images();
+        images[1].sample_count = 4;
+        let mut desc = pass_desc(&[1], None);
+        assert_eq!(
+            validate(&desc, &images, &[]),
+            Err(PassValidationError::MSAANotSupported(
+                PassAttachment::Color(0)
+            ))
+        );
+        desc.color_attachments[0].resolve = false;
+        assert_eq!(validate(&desc, &images, ALL_PASS_FEATURES), Ok(()));
+
+        let images = pass_images();
+        assert_eq!(
+            validate(&desc, &images, ALL_PASS_FEATURES),
+            Err(PassValidationError::NoStoreTarget(PassAttachment::Color(0)))
+        );
+    }
+
+    #[test]
+    fn validate_pass_rejects_out_of_range_subimages() {
+        let images = pass_images();
+        let color = PassAttachment::Color(0);
+        let mut desc = pass_desc(&[1], None);
+        desc.color_attachments[0].mip_level = 1;
+        assert_eq!(
+            validate(&desc, &images, &[]),
+            Err(PassValidationError::InvalidMipLevel(color))
+        );
+
+        let mut desc = pass_desc(&[1], None);
+        desc.color_attachments[0].index = 1;
+        assert_eq!(
+            validate(&desc, &images, &[]),
+            Err(PassValidationError::InvalidIndex(PassAttachment::Color(0)))
+        );
+
+        let mut images = pass_images();
+        images[1].image_type = ImageType::Cube;
+        desc.color_attachments[0].index = 5;
+        assert_eq!(validate(&desc, &images, &[]), Ok(()));
+        desc.color_attachments[0].index = 6;
+        assert_eq!(
+            validate(&desc, &images, &[]),
+            Err(PassValidationError::InvalidIndex(PassAttachment::Color(0)))
+        );
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    struct Mat4([[f32; 4]; 4]);
+
+    #[allow(unsafe_code)]
+    unsafe impl Pod for Mat4 {}
+    impl Uniforms for Mat4 {}
+
+    #[test]
+    fn uniforms_block_size_of_a_mat4() {
+        let mvp = Mat4([[0.0; 4]; 4]);
+        assert_eq!(Mat4::block_size(), 64);
+        assert_eq!(mvp.as_bytes().len(), 64);
+        assert_eq!(<[[f32; 4]; 4]>::block_size(), 64);
+        assert_eq!(<[f32; 4]>::block_size(), 16);
+    }
+
+    #[test]
+    fn subimage_content_from_slice_of_floats() {
+        let content = SubimageContent::from_slice(PixelFormat::RGBA32F, &[0f32; 4]).unwrap();
+        assert_eq!(content.content.len(), 16);
+        let content = SubimageContent::from_slice(PixelFormat::RGBA32F, &[[0f32; 4]; 2]).unwrap();
+        assert_eq!(content.content.len(), 32);
+        let content = SubimageContent::from_slice(PixelFormat::R16F, &[0u16; 3]).unwrap();
+        assert_eq!(content.content.len(), 6);
+    }
+
+    #[test]
+    fn subimage_content_from_slice_rejects_mismatched_components() {
+        assert!(SubimageContent::from_slice(PixelFormat::RGBA32F, &[0u8; 16]).is_none());
+        assert!(SubimageContent::from_slice(PixelFormat::RGBA8, &[0u16; 4]).is_none());
+        assert!(SubimageContent::from_slice(PixelFormat::RGBA16F, &[0f32; 4]).is_none());
+        assert!(SubimageContent::from_slice(PixelFormat::Depth, &[0f32; 4]).is_none());
+        assert!(SubimageContent::from_slice(PixelFormat::RGBA8, &[[0u8; 4]; 2]).is_some());
+    }
+
+    #[test]
+    fn scissor_rects_are_clamped_before_flipping() {
+        let (x, y, width, height) = clamp_rect(640, 480, 600, 400, 100, 100);
+        assert_eq!((x, y, width, height), (600, 400, 40, 80));
+        assert_eq!(flip_y(480, y, height), 0);
+    }
+
+    fn vertex_attr(name: &'static str, offset: u32, format: VertexFormat) -> VertexAttrDesc {
+        VertexAttrDesc {
+            name: Cow::Borrowed(name),
+            sem_name: Cow::Borrowed(""),
+            sem_index: 0,
+            offset,
+            format,
+        }
+    }
+
+    fn vertex_layout(stride: u32, attrs: Vec<VertexAttrDesc>) -> VertexLayoutDesc {
+        let mut attrs = attrs.into_iter();
+        VertexLayoutDesc {
+            stride,
+            step_func: VertexStep::PerVertex,
+            step_rate: 1,
+            attrs: ::std::array::from_fn(|_| {
+                attrs
+                    .next()
+                    .unwrap_or_else(|| vertex_attr("", 0, VertexFormat::Float))
+            }),
+        }
+    }
+
+    #[test]
+    fn resolve_vertex_layouts_packs_attributes_in_order() {
+        let mut layouts = [vertex_layout(
+            0,
+            vec![
+                vertex_attr("position", 0, VertexFormat::Float3),
+                vertex_attr("texcoord", 0, VertexFormat::Float2),
+            ],
+        )];
+        resolve_vertex_layouts(&mut layouts);
+        assert_eq!(layouts[0].attrs[0].offset, 0);
+        assert_eq!(layouts[0].attrs[1].offset, 12);
+        assert_eq!(layouts[0].attrs[2].offset, 0);
+        assert_eq!(layouts[0].stride, 20);
+    }
+
+    #[test]
+    fn resolve_vertex_layouts_keeps_explicit_offsets_and_strides() {
+        let mut layouts = [
+            vertex_layout(
+                0,
+                vec![
+                    vertex_attr("texcoord", 16, VertexFormat::Float2),
+                    vertex_attr("position", 0, VertexFormat::Float3),
+                ],
+            ),
+            vertex_layout(
+                32,
+                vec![
+                    vertex_attr("position", 0, VertexFormat::Float3),
+                    vertex_attr("texcoord", 0, VertexFormat::Float2),
+                ],
+            ),
+        ];
+        resolve_vertex_layouts(&mut layouts);
+        assert_eq!(layouts[0].attrs[0].offset, 16);
+        assert_eq!(layouts[0].attrs[1].offset, 0);
+        assert_eq!(layouts[0].stride, 24);
+        assert_eq!(layouts[1].attrs[1].offset, 12);
+        assert_eq!(layouts[1].stride, 32);
+    }
+
+    #[test]
+    fn context_rejects_invalid_pool_sizes_before_creating_the_backend() {
+        let config = Config {
+            buffer_pool_size: 0,
+            ..Config::default()
+        };
+        assert_eq!(
+            Context::try_new(config).err(),
+            Some(ConfigError::InvalidPoolSize {
+                pool: "buffer",
+                size: 0,
+            })
+        );
+        let config = Config {
+            pass_pool_size: pool::MAX_POOL_SIZE,
+            ..Config::default()
+        };
+        assert_eq!(
+            Context::try_new(config).err(),
+            Some(ConfigError::InvalidPoolSize {
+                pool: "pass",
+                size: pool::MAX_POOL_SIZE,
+            })
+        );
+    }
+
+    #[test]
+    fn handle_ids_round_trip_through_raw() {
+        assert!(!Buffer::default().is_valid());
+        assert_eq!(Image::default().raw(), 0);
+        assert!(!Pass::from_raw(0).is_valid());
+
+        let id = (7 << pool::SLOT_SHIFT) | 3;
+        assert!(Buffer::from_raw(id).is_valid());
+        assert_eq!(Buffer::from_raw(id).raw(), id);
+        assert_eq!(Image::from_raw(id).raw(), id);
+        assert_eq!(Shader::from_raw(id).raw(), id);
+        assert_eq!(Pipeline::from_raw(id).raw(), id);
+        assert_eq!(Pass::from_raw(id).raw(), id);
+        assert_eq!(Pipeline::from_raw(id), Pipeline::with(id));
+    }
+
+    #[test]
+    fn equal_handles_dedupe_in_a_hash_set() {
+        use std::collections::HashSet;
+
+        let handles = [
+            Image::from_raw(0x10001),
+            Image::from_raw(0x10002),
+            Image::from_raw(0x10001),
+            Image::from_raw(0x20001),
+            Image::default(),
+        ];
+        let unique: HashSet<Image> = handles.iter().cloned().collect();
+        assert_eq!(unique.len(), 4);
+        assert!(unique.contains(&Image::from_raw(0x10001)));
+        assert!(unique.contains(&Image::default()));
+    }
+
+    #[test]
+    fn index_type_bytesize_gives_the_base_element_offset() {
+        assert_eq!(IndexType::UInt16.bytesize(), 2);
+        assert_eq!(IndexType::UInt32.bytesize(), 4);
+        assert_eq!(3 * IndexType::UInt16.bytesize(), 6);
+        assert_eq!(3 * IndexType::UInt32.bytesize(), 12);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn pipeline_desc_round_trips_through_serde() {
+        let desc = PipelineDesc {
+            vertex_layouts: [
+                vertex_layout(
+                    20,
+                    vec![
+                        vertex_attr("position", 0, VertexFormat::Float3),
+                        vertex_attr("texcoord", 12, VertexFormat::Float2),
+                    ],
+                ),
+                vertex_layout(0, vec![]),
+                vertex_layout(0, vec![]),
+                vertex_layout(0, vec![]),
+            ],
+            shader: Shader::from_raw(0x10001),
+            primitive_type: PrimitiveType::TriangleStrip,
+            index_type: Some(IndexType::UInt16),
+            primitive_restart: true,
+            depth_stencil: DepthStencilState::default(),
+            blend: BlendState::default(),
+            attachment_blends: None,
+            rasterizer: RasterizerState::default(),
+        };
+        let json = serde_json::to_string(&desc).unwrap();
+        let round_tripped: PipelineDesc = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&round_tripped).unwrap(), json);
+
+        let layout = &round_tripped.vertex_layouts[0];
+        assert_eq!(layout.stride, 20);
+        assert_eq!(layout.attrs[1].name, "texcoord");
+        assert_eq!(layout.attrs[1].offset, 12);
+        assert_eq!(layout.attrs[1].format, VertexFormat::Float2);
+        assert_eq!(round_tripped.shader, Shader::from_raw(0x10001));
+        assert_eq!(round_tripped.primitive_type, PrimitiveType::TriangleStrip);
+        assert_eq!(round_tripped.index_type, Some(IndexType::UInt16));
+        assert!(round_tripped.primitive_restart);
+    }
+
+    #[test]
+    fn image_content_size_of_a_mipmapped_cube() {
+        let mut cube = render_target(PixelFormat::RGBA8, 16, 16, 1);
+        cube.image_type = ImageType::Cube;
+        cube.num_mipmaps = 2;
+        assert_eq!(image_content_size(&cube), Some(6 * (16 * 16 + 8 * 8) * 4));
+
+        cube.pixel_format = PixelFormat::Depth;
+        assert_eq!(image_content_size(&cube), None);
+    }
+
+    #[cfg(feature = "gl")]
+    #[test]
+    fn lost_context_fails_live_resources() {
+        let mut pool = pool::Pool::<Image>::new(4).unwrap();
+        let live = pool.alloc().unwrap();
+        pool.insert(live, render_target(PixelFormat::RGBA8, 16, 16, 1));
+        let allocated = pool.alloc().unwrap();
+        let released = pool.alloc().unwrap();
+        pool.insert(released, render_target(PixelFormat::RGBA8, 16, 16, 1));
+        pool.release(released);
+
+        fail_resources(&mut pool, |img| &mut img.slot);
+        let state = |img: &backend::ImageResource| img.slot.state;
+        assert_eq!(pool.state(&live, state), ResourceState::Failed);
+        assert_eq!(pool.state(&allocated, state), ResourceState::Alloc);
+        assert_eq!(pool.state(&released, state), ResourceState::Initial);
+    }
+
+    #[test]
+    fn all_features_lists_every_feature_once() {
+        // A new `Feature` variant fails to compile here until it gets an
+        // index, which then has to be listed in `ALL_FEATURES` as well.
+        fn index(feature: Feature) -> usize {
+            match feature {
+                Feature::Instancing => 0,
+                Feature::TextureCompressionDXT => 1,
+                Feature::TextureCompressionPVRTC => 2,
+                Feature::TextureCompressionATC => 3,
+                Feature::TextureCompressionETC2 => 4,
+                Feature::TextureFloat => 5,
+                Feature::TextureHalfFloat => 6,
+                Feature::OriginBottomLeft => 7,
+                Feature::OriginTopLeft => 8,
+                Feature::MSAARenderTargets => 9,
+                Feature::PackedVertexFormat_10_2 => 10,
+                Feature::MultipleRenderTarget => 11,
+                Feature::ImageType3D => 12,
+                Feature::ImageTypeArray => 13,
+            }
+        }
+        assert_eq!(ALL_FEATURES.len(), 14);
+        for (i, &feature) in ALL_FEATURES.iter().enumerate() {
+            assert_eq!(index(feature), i, "{:?}", feature);
+        }
+    }
+}
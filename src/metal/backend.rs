@@ -5,14 +5,48 @@
 // except according to those terms.
 
 use std::os;
+use std::ptr;
 
-use {Config, Feature, ShaderStage};
+use metal_sys::{Buffer, MTLPixelFormat, MTLResourceOptions};
 
-pub struct Backend {}
+use metal::{BufferResource, ImageResource, PassResource, PipelineResource, ShaderResource};
+use {
+    BackendInfo, BufferDesc, Config, Feature, FeatureSupport, ImageContent, ImageDesc, Limits,
+    PassAction, PassDesc, PipelineDesc, PixelFormat, PixelFormatInfo, ResourceError, ShaderDesc,
+    ShaderStage, NUM_INFLIGHT_FRAMES,
+};
+
+/// Metal requires uniform buffer offsets to be aligned to 256 bytes.
+const UB_ALIGNMENT: usize = 256;
+
+pub struct Backend {
+    /// One uniform buffer per in-flight frame, used as a ring: uniform
+    /// blocks are appended during the frame and the next buffer is used
+    /// after `commit`.
+    uniform_buffers: Vec<Buffer>,
+    ub_size: usize,
+    cur_ub_offset: usize,
+    frame_rotate_index: usize,
+    device_name: String,
+}
 
 impl Backend {
     pub fn new(desc: Config) -> Self {
-        Backend {}
+        let uniform_buffers = (0..NUM_INFLIGHT_FRAMES)
+            .map(|_| {
+                desc.mtl_device.new_buffer(
+                    desc.mtl_global_uniform_buffer_size as u64,
+                    MTLResourceOptions::CPUCacheModeWriteCombined,
+                )
+            })
+            .collect();
+        Backend {
+            uniform_buffers: uniform_buffers,
+            ub_size: desc.mtl_global_uniform_buffer_size,
+            cur_ub_offset: 0,
+            frame_rotate_index: 0,
+            device_name: desc.mtl_device.name().to_string(),
+        }
     }
 
     pub fn query_feature(&self, feature: Feature) -> bool {
@@ -33,30 +67,215 @@ impl Backend {
         }
     }
 
+    pub fn query_feature_detail(&self, feature: Feature) -> FeatureSupport {
+        if self.query_feature(feature) {
+            FeatureSupport::Supported
+        } else {
+            FeatureSupport::UnsupportedByBackend
+        }
+    }
+
+    pub fn query_limits(&self) -> Limits {
+        // The limits of the lowest GPU family of each platform, see the
+        // Metal feature set tables.
+        let max_image_size = if cfg!(target_os = "macos") {
+            16384
+        } else {
+            8192
+        };
+        Limits {
+            max_image_size_2d: max_image_size,
+            max_image_size_cube: max_image_size,
+            max_image_size_3d: 2048,
+            max_image_array_layers: 2048,
+            max_vertex_attributes: ::MAX_VERTEX_ATTRIBUTES,
+            // Uniform blocks are appended to the global uniform buffer.
+            max_uniform_block_size: self.ub_size,
+            max_sample_count: 4,
+        }
+    }
+
+    pub fn query_pixelformat(&self, fmt: PixelFormat) -> PixelFormatInfo {
+        if fmt.is_valid_rendertarget_depth_format() {
+            return PixelFormatInfo {
+                sample: true,
+                render: true,
+                msaa: true,
+                ..PixelFormatInfo::default()
+            };
+        }
+        if matches!(fmt.mtl_texture_format(), MTLPixelFormat::Invalid) {
+            return PixelFormatInfo::default();
+        }
+        // 32-bit float formats can't be filtered or blended by iOS GPUs.
+        let is_float32 = fmt == PixelFormat::RGBA32F || fmt == PixelFormat::R32F;
+        let float32_ok = cfg!(target_os = "macos");
+        let render = !matches!(fmt.mtl_rendertarget_color_format(), MTLPixelFormat::Invalid);
+        PixelFormatInfo {
+            sample: true,
+            filter: !is_float32 || float32_ok,
+            render,
+            blend: render && (!is_float32 || float32_ok),
+            msaa: render && (!is_float32 || float32_ok),
+        }
+    }
+
+    pub fn query_backend_info(&self) -> BackendInfo {
+        // Metal has no version string, the feature set is implied by the
+        // device.
+        BackendInfo {
+            name: "Metal",
+            version: String::new(),
+            renderer: self.device_name.clone(),
+            force_gles2: false,
+        }
+    }
+
+    pub fn query_line_width_range(&self) -> (f32, f32) {
+        // Metal always rasterizes lines one pixel wide.
+        (1.0, 1.0)
+    }
+
     pub fn reset_state_cache(&mut self) {
-        unimplemented!();
+        // Render state lives in the command encoder, which is recreated for
+        // every pass, so there's no cache to invalidate.
     }
 
-    pub fn apply_viewport(
+    pub fn begin_pass(
         &mut self,
-        x: u32,
-        y: u32,
+        pass: Option<&PassResource>,
+        action: &PassAction,
         width: u32,
         height: u32,
-        origin_top_left: bool,
     ) {
-        unimplemented!();
+        // Unsupported: render command encoders aren't created yet, so
+        // there's nothing to begin.
+    }
+
+    pub fn apply_viewport(&mut self, x: u32, y: u32, width: u32, height: u32, flip: bool) {
+        // Unsupported: passes don't create render command encoders yet, so
+        // there's no encoder to set the viewport on.
+    }
+
+    pub fn apply_scissor_rect(&mut self, x: u32, y: u32, width: u32, height: u32, flip: bool) {
+        // Unsupported: passes don't create render command encoders yet, so
+        // there's no encoder to set the scissor rect on.
     }
 
-    pub fn apply_scissor_rect(
+    pub fn disable_scissor(&mut self) {}
+
+    pub fn read_pixels(
         &mut self,
         x: u32,
         y: u32,
         width: u32,
         height: u32,
-        origin_top_left: bool,
+        format: PixelFormat,
+        out: &mut [u8],
+    ) -> Result<(), ResourceError> {
+        Err(ResourceError::Unsupported)
+    }
+
+    pub fn get_buffer_data(
+        &mut self,
+        buf: &BufferResource,
+        offset: usize,
+        out: &mut [u8],
+    ) -> Result<(), ResourceError> {
+        Err(ResourceError::Unsupported)
+    }
+
+    pub fn create_buffer(&mut self, desc: &BufferDesc) -> Option<BufferResource> {
+        warn!("make_buffer: buffers are not supported by the Metal backend yet");
+        None
+    }
+
+    pub fn destroy_buffer(&mut self, buf: BufferResource) {
+        // create_buffer() never succeeds, so there's nothing to release.
+    }
+
+    pub fn create_image(&mut self, desc: &ImageDesc) -> Option<ImageResource> {
+        warn!("make_image: images are not supported by the Metal backend yet");
+        None
+    }
+
+    pub fn destroy_image(&mut self, img: ImageResource) {
+        // create_image() never succeeds, so there's nothing to release.
+    }
+
+    pub fn create_shader(&mut self, desc: &ShaderDesc) -> Option<ShaderResource> {
+        warn!("make_shader: shaders are not supported by the Metal backend yet");
+        None
+    }
+
+    pub fn destroy_shader(&mut self, shd: ShaderResource) {
+        // create_shader() never succeeds, so there's nothing to release.
+    }
+
+    pub fn create_pass(
+        &mut self,
+        desc: &PassDesc,
+        color_images: Vec<ImageResource>,
+        ds_image: Option<ImageResource>,
+    ) -> Option<PassResource> {
+        warn!("make_pass: offscreen passes are not supported by the Metal backend yet");
+        None
+    }
+
+    pub fn destroy_pass(&mut self, pass: PassResource) {
+        // create_pass() never succeeds, so there's nothing to release.
+    }
+
+    pub fn destroy_sampler_cache(&mut self) {
+        // Sampler states are owned by their images, there's no shared cache.
+    }
+
+    pub fn create_pipeline(
+        &mut self,
+        desc: PipelineDesc,
+        shader: ShaderResource,
+    ) -> Option<PipelineResource> {
+        warn!("make_pipeline: pipelines are not supported by the Metal backend yet");
+        None
+    }
+
+    pub fn update_buffer(&mut self, buf: &mut BufferResource, frame_index: u32, data: &[u8]) {}
+
+    pub fn append_buffer(
+        &mut self,
+        buf: &mut BufferResource,
+        frame_index: u32,
+        data: &[u8],
+    ) -> Option<usize> {
+        None
+    }
+
+    pub fn update_image(&mut self, img: &mut ImageResource, frame_index: u32, data: &ImageContent) {
+    }
+
+    pub fn generate_mipmaps(&mut self, img: &ImageResource) -> Result<(), ResourceError> {
+        Err(ResourceError::Unsupported)
+    }
+
+    pub fn apply_draw_state(
+        &mut self,
+        pip: &PipelineResource,
+        vbs: &[&BufferResource],
+        ib: Option<&BufferResource>,
+        vs_imgs: &[&ImageResource],
+        fs_imgs: &[&ImageResource],
     ) {
-        unimplemented!();
+        // Without pipelines there's no draw state to apply.
+    }
+
+    pub fn apply_blend_color(&mut self, color: [f32; 4]) {
+        // Unsupported: passes don't create render command encoders yet, so
+        // there's no encoder to set the blend color on.
+    }
+
+    pub fn apply_stencil_ref(&mut self, stencil_ref: u8) {
+        // Unsupported: passes don't create render command encoders yet, so
+        // there's no encoder to set the stencil reference value on.
     }
 
     pub fn apply_uniform_block(
@@ -66,18 +285,71 @@ impl Backend {
         data: *const os::raw::c_void,
         num_bytes: u32,
     ) {
-        unimplemented!();
+        let num_bytes = num_bytes as usize;
+        let offset = self.cur_ub_offset;
+        let next_offset = match next_ub_offset(offset, num_bytes, self.ub_size) {
+            Some(next_offset) => next_offset,
+            None => {
+                warn!(
+                    "Metal uniform buffer overflow ({} bytes), dropping uniform block",
+                    self.ub_size
+                );
+                return;
+            }
+        };
+        let buffer = &self.uniform_buffers[self.frame_rotate_index];
+        #[allow(unsafe_code)]
+        unsafe {
+            let dst = (buffer.contents() as *mut u8).add(offset);
+            ptr::copy_nonoverlapping(data as *const u8, dst, num_bytes);
+        }
+        // Binding the block at `offset` is unsupported until passes create
+        // render command encoders.
+        self.cur_ub_offset = next_offset;
     }
 
-    pub fn draw(&mut self, base_element: u32, num_elements: u32, num_instances: u32) {
-        unimplemented!();
+    pub fn draw(&mut self, base_element: u32, num_elements: u32, num_instances: u32) {}
+
+    pub fn end_pass(&mut self, pass: Option<&PassResource>) {}
+
+    pub fn commit(&mut self) {
+        self.frame_rotate_index = (self.frame_rotate_index + 1) % NUM_INFLIGHT_FRAMES;
+        self.cur_ub_offset = 0;
     }
+}
 
-    pub fn end_pass(&mut self) {
-        unimplemented!();
+/// The offset of the next uniform block in a uniform buffer of `ub_size`
+/// bytes, after copying a block of `num_bytes` to `offset`, or `None` if
+/// the block doesn't fit.
+///
+/// The next offset is rounded up to `UB_ALIGNMENT`.
+fn next_ub_offset(offset: usize, num_bytes: usize, ub_size: usize) -> Option<usize> {
+    let end = offset.checked_add(num_bytes)?;
+    if end > ub_size {
+        return None;
     }
+    Some(end.saturating_add(UB_ALIGNMENT - 1) & !(UB_ALIGNMENT - 1))
+}
 
-    pub fn commit(&mut self) {
-        unimplemented!();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_blocks_are_aligned_to_256_bytes() {
+        assert_eq!(next_ub_offset(0, 1, 4096), Some(256));
+        assert_eq!(next_ub_offset(0, 64, 4096), Some(256));
+        assert_eq!(next_ub_offset(0, 256, 4096), Some(256));
+        assert_eq!(next_ub_offset(256, 257, 4096), Some(768));
+        assert_eq!(next_ub_offset(512, 0, 4096), Some(512));
+    }
+
+    #[test]
+    fn uniform_blocks_that_overflow_the_buffer_are_dropped() {
+        assert_eq!(next_ub_offset(3840, 256, 4096), Some(4096));
+        assert_eq!(next_ub_offset(3840, 257, 4096), None);
+        assert_eq!(next_ub_offset(4096, 1, 4096), None);
+        assert_eq!(next_ub_offset(usize::MAX, 1, usize::MAX), None);
+        assert_eq!(next_ub_offset(1, usize::MAX, usize::MAX), None);
     }
 }
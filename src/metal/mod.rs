@@ -19,29 +19,49 @@ use pool;
 
 #[derive(Debug, Default)]
 pub struct BufferResource {
-    slot: pool::Slot,
-    size: usize,
-    buffer_type: BufferType,
-    usage: Usage,
+    pub slot: pool::Slot,
+    pub size: usize,
+    pub buffer_type: BufferType,
+    pub usage: Usage,
     upd_frame_index: u32,
+    append_frame_index: u32,
+    append_pos: usize,
     num_slots: usize,
     active_slot: usize,
     mtl_buf: [u32; NUM_INFLIGHT_FRAMES],
 }
 
-#[derive(Debug, Default)]
+impl BufferResource {
+    /// The frame index of the last update of the buffer, or 0 if it was
+    /// never updated.
+    pub fn upd_frame_index(&self) -> u32 {
+        self.upd_frame_index
+    }
+
+    /// The byte offset at which the next append during frame `frame_index`
+    /// writes its data.
+    pub fn append_pos(&self, frame_index: u32) -> usize {
+        if self.append_frame_index == frame_index {
+            self.append_pos
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct ImageResource {
-    slot: pool::Slot,
-    image_type: ImageType,
-    render_target: bool,
-    width: usize,
-    height: usize,
-    depth: usize,
-    num_mipmaps: usize,
-    usage: Usage,
-    pixel_format: PixelFormat,
-    sample_count: usize,
-    min_filter: Filter,
+    pub slot: pool::Slot,
+    pub image_type: ImageType,
+    pub render_target: bool,
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub num_mipmaps: usize,
+    pub usage: Usage,
+    pub pixel_format: PixelFormat,
+    pub sample_count: usize,
+    pub min_filter: Filter,
     mag_filter: Filter,
     wrap_u: Wrap,
     wrap_v: Wrap,
@@ -56,17 +76,17 @@ pub struct ImageResource {
     mtl_sampler_state: u32,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct UniformBlock {
     size: usize,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct ShaderImage {
     image_type: ImageType,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct ShaderStage {
     num_uniform_blocks: usize,
     num_images: usize,
@@ -76,15 +96,31 @@ pub struct ShaderStage {
     mt_func: u32,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct ShaderResource {
-    slot: pool::Slot,
+    pub slot: pool::Slot,
     stage: [ShaderStage; NUM_SHADER_STAGES],
 }
 
+impl ShaderResource {
+    /// The size in bytes of uniform block `index` of `stage`, or `None` if
+    /// the shader has no such uniform block.
+    pub fn uniform_block_size(&self, stage: ::ShaderStage, index: usize) -> Option<usize> {
+        let stage = match stage {
+            ::ShaderStage::VS => &self.stage[0],
+            ::ShaderStage::FS => &self.stage[1],
+        };
+        if index < stage.num_uniform_blocks {
+            Some(stage.uniform_blocks[index].size)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PipelineResource {
-    slot: pool::Slot,
+    pub slot: pool::Slot,
     shader: ShaderResource, // FIXME: Should be a reference, not something owned.
     shader_id: Shader,
     vertex_layout_valid: [bool; MAX_SHADERSTAGE_BUFFERS],
@@ -96,7 +132,7 @@ pub struct PipelineResource {
     depth_bias_slope_scale: f32,
     depth_bias_clamp: f32,
     mtl_prim_type: MTLPrimitiveType,
-    index_type: IndexType,
+    index_type: Option<IndexType>,
     mtl_index_size: u32,
     mtl_index_type: MTLIndexType,
     mtl_cull_mode: MTLCullMode,
@@ -122,7 +158,7 @@ impl Default for PipelineResource {
             depth_bias_slope_scale: 0.0f32,
             depth_bias_clamp: 0.0f32,
             mtl_prim_type: MTLPrimitiveType::Point,
-            index_type: IndexType::UInt16,
+            index_type: None,
             mtl_index_size: 0,
             mtl_index_type: MTLIndexType::UInt16,
             mtl_cull_mode: MTLCullMode::None,
@@ -135,9 +171,44 @@ impl Default for PipelineResource {
     }
 }
 
+impl PipelineResource {
+    /// Whether the pipeline reads vertex data from vertex buffer slot `index`.
+    pub fn uses_vertex_buffer(&self, index: usize) -> bool {
+        self.vertex_layout_valid
+            .get(index)
+            .cloned()
+            .unwrap_or(false)
+    }
+
+    /// The index type of the pipeline, or `None` for non-indexed rendering.
+    pub fn index_type(&self) -> Option<IndexType> {
+        self.index_type
+    }
+
+    /// Metal binds vertex attributes by index, so there are no named
+    /// attribute locations.
+    pub fn attrib_locations(&self) -> &[(String, i32)] {
+        &[]
+    }
+
+    /// The image type the pipeline's shader expects in image slot `index` of
+    /// `stage`, or `None` if the slot is unused.
+    pub fn shader_image_type(&self, stage: ::ShaderStage, index: usize) -> Option<ImageType> {
+        let stage = match stage {
+            ::ShaderStage::VS => &self.shader.stage[0],
+            ::ShaderStage::FS => &self.shader.stage[1],
+        };
+        if index < stage.num_images {
+            Some(stage.images[index].image_type)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Attachment {
-    image: ImageResource, // FIXME: Should be a reference, not something owned.
+    pub image: ImageResource, // FIXME: Should be a reference, not something owned.
     image_id: Image,
     mip_level: u32,
     slice: u32,
@@ -145,8 +216,41 @@ pub struct Attachment {
 
 #[derive(Debug, Default)]
 pub struct PassResource {
-    slot: pool::Slot,
+    pub slot: pool::Slot,
     num_color_atts: u32,
-    color_atts: [Attachment; MAX_COLOR_ATTACHMENTS],
+    pub color_atts: [Attachment; MAX_COLOR_ATTACHMENTS],
     ds_att: Attachment,
 }
+
+impl PassResource {
+    /// The width and height of the images the pass renders into.
+    pub fn size(&self) -> (usize, usize) {
+        let image = if self.num_color_atts > 0 {
+            &self.color_atts[0].image
+        } else {
+            &self.ds_att.image
+        };
+        (image.width, image.height)
+    }
+
+    /// The images the pass renders into.
+    pub fn attachments(&self) -> ::PassAttachments {
+        let image = |att: &Attachment| ::AttachmentImage {
+            image: att.image_id,
+            mip_level: att.mip_level as usize,
+            index: att.slice as usize,
+        };
+        let num_color_atts = self.num_color_atts as usize;
+        ::PassAttachments {
+            color_attachments: self.color_atts[..num_color_atts]
+                .iter()
+                .map(&image)
+                .collect(),
+            depth_stencil_attachment: if self.ds_att.image_id.is_valid() {
+                Some(image(&self.ds_att))
+            } else {
+                None
+            },
+        }
+    }
+}
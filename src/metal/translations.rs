@@ -170,10 +170,7 @@ impl IndexType {
     ///
     /// This is only present when the `metal` feature is enabled.
     pub fn mtl_index_size(self) -> usize {
-        match self {
-            IndexType::UInt16 => 2,
-            IndexType::UInt32 => 4,
-        }
+        self.bytesize()
     }
 
     /// Convert this index type to the Metal equivalent `MTLIndexType`.
@@ -208,12 +205,16 @@ impl PixelFormat {
     pub fn mtl_texture_format(self) -> MTLPixelFormat {
         match self {
             PixelFormat::RGBA8 => MTLPixelFormat::RGBA8Unorm,
+            PixelFormat::BGRA8 => MTLPixelFormat::BGRA8Unorm,
             PixelFormat::R10G10B10A2 => MTLPixelFormat::RGB10A2Unorm,
             PixelFormat::RGBA32F => MTLPixelFormat::RGBA32Float,
             PixelFormat::RGBA16F => MTLPixelFormat::RGBA16Float,
+            PixelFormat::R11G11B10F => MTLPixelFormat::RG11B10Float,
+            PixelFormat::RGB9E5 => MTLPixelFormat::RGB9E5Float,
             PixelFormat::R32F => MTLPixelFormat::R32Float,
             PixelFormat::R16F => MTLPixelFormat::R16Float,
-            PixelFormat::L8 => MTLPixelFormat::R8Unorm,
+            PixelFormat::L8 | PixelFormat::R8 => MTLPixelFormat::R8Unorm,
+            PixelFormat::RG8 => MTLPixelFormat::RG8Unorm,
             #[cfg(target_os = "macos")]
             PixelFormat::DXT1 => MTLPixelFormat::BC1_RGBA,
             #[cfg(target_os = "macos")]
@@ -245,7 +246,10 @@ impl PixelFormat {
             PixelFormat::RGBA8 => MTLPixelFormat::BGRA8Unorm, // Not a bug!
             PixelFormat::RGBA32F => MTLPixelFormat::RGBA32Float,
             PixelFormat::RGBA16F => MTLPixelFormat::RGBA16Float,
+            PixelFormat::R11G11B10F => MTLPixelFormat::RG11B10Float,
             PixelFormat::R10G10B10A2 => MTLPixelFormat::RGB10A2Unorm,
+            PixelFormat::R8 => MTLPixelFormat::R8Unorm,
+            PixelFormat::RG8 => MTLPixelFormat::RG8Unorm,
             _ => MTLPixelFormat::Invalid,
         }
     }
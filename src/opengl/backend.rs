@@ -4,29 +4,191 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use opengl::gleam::gl::types::{GLenum, GLint, GLuint};
+use opengl::gleam::gl::types::{
+    GLbitfield, GLboolean, GLenum, GLfloat, GLint, GLintptr, GLsizei, GLsizeiptr, GLsync, GLuint,
+    GLuint64,
+};
 use opengl::gleam::gl::{self, Gl};
 use opengl::*;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::os;
+use std::ptr;
 
-use {Config, Feature, ShaderStage};
+use {clamp_rect, flip_y};
+use {
+    Action, BackendInfo, BufferDesc, Config, Feature, FeatureSupport, ImageContent, ImageDesc,
+    ImageType, Limits, PassAction, PassDesc, PipelineDesc, PixelFormat, PixelFormatInfo,
+    ResourceError, ResourceState, ShaderDesc, ShaderStage, ShaderStageDesc, Usage, VertexStep,
+};
 
 const GL_TEXTURE_MAX_ANISOTROPY_EXT: GLuint = 0x84FE;
 const GL_MAX_TEXTURE_MAX_ANISOTROPY_EXT: GLuint = 0x84FF;
+const GL_PRIMITIVE_RESTART: GLenum = 0x8F9D;
+const GL_PRIMITIVE_RESTART_FIXED_INDEX: GLenum = 0x8D69;
+const GL_TEXTURE_CUBE_MAP_SEAMLESS: GLenum = 0x884F;
+const GL_DEPTH_CLAMP: GLenum = 0x864F;
+const GL_TEXTURE_SWIZZLE: [GLenum; 4] = [0x8E42, 0x8E43, 0x8E44, 0x8E45];
+
+/// The features that GLES3 and GL 3.3 always support, but GLES2 doesn't.
+const GLES3_FEATURES: [Feature; 8] = [
+    Feature::Instancing,
+    Feature::TextureHalfFloat,
+    Feature::TextureFloat,
+    Feature::MSAARenderTargets,
+    Feature::PackedVertexFormat_10_2,
+    Feature::MultipleRenderTarget,
+    Feature::ImageType3D,
+    Feature::ImageTypeArray,
+];
+
+/// How long `commit()` waits for the GPU to release an inflight frame, in
+/// nanoseconds.
+const FRAME_FENCE_TIMEOUT: GLuint64 = 1_000_000_000;
+
+/// `glPolygonMode()`, which gleam doesn't expose.
+type PolygonModeFn = unsafe extern "system" fn(face: GLenum, mode: GLenum);
+
+/// `glGetFloatv()`, as gleam only returns the first value of a query.
+type GetFloatvFn = unsafe extern "system" fn(pname: GLenum, data: *mut GLfloat);
+
+/// `glRenderbufferStorageMultisample()`, which gleam doesn't expose.
+type RenderbufferStorageMultisampleFn = unsafe extern "system" fn(
+    target: GLenum,
+    samples: GLsizei,
+    internal_format: GLenum,
+    width: GLsizei,
+    height: GLsizei,
+);
+
+/// `glPrimitiveRestartIndex()`, which gleam doesn't expose.
+type PrimitiveRestartIndexFn = unsafe extern "system" fn(index: GLuint);
+
+/// `glTexStorage2D()`, which gleam doesn't expose.
+type TexStorage2DFn = unsafe extern "system" fn(
+    target: GLenum,
+    levels: GLsizei,
+    internal_format: GLenum,
+    width: GLsizei,
+    height: GLsizei,
+);
+
+/// `glTexStorage3D()`, which gleam doesn't expose.
+type TexStorage3DFn = unsafe extern "system" fn(
+    target: GLenum,
+    levels: GLsizei,
+    internal_format: GLenum,
+    width: GLsizei,
+    height: GLsizei,
+    depth: GLsizei,
+);
+
+/// `glGenSamplers()`, which gleam doesn't expose.
+type GenSamplersFn = unsafe extern "system" fn(count: GLsizei, samplers: *mut GLuint);
+
+/// `glDeleteSamplers()`, which gleam doesn't expose.
+type DeleteSamplersFn = unsafe extern "system" fn(count: GLsizei, samplers: *const GLuint);
+
+/// `glBindSampler()`, which gleam doesn't expose.
+type BindSamplerFn = unsafe extern "system" fn(unit: GLuint, sampler: GLuint);
+
+/// `glSamplerParameteri()`, which gleam doesn't expose.
+type SamplerParameteriFn = unsafe extern "system" fn(sampler: GLuint, pname: GLenum, param: GLint);
+
+/// `glSamplerParameterf()`, which gleam doesn't expose.
+type SamplerParameterfFn =
+    unsafe extern "system" fn(sampler: GLuint, pname: GLenum, param: GLfloat);
+
+/// The GL 3.3 sampler object functions.
+#[derive(Copy, Clone)]
+struct SamplerFns {
+    gen_samplers: GenSamplersFn,
+    delete_samplers: DeleteSamplersFn,
+    bind_sampler: BindSamplerFn,
+    sampler_parameter_i: SamplerParameteriFn,
+    sampler_parameter_f: SamplerParameterfFn,
+}
+
+/// `glEnablei()` and `glDisablei()`, which gleam doesn't expose.
+type EnableiFn = unsafe extern "system" fn(cap: GLenum, index: GLuint);
+
+/// `glBlendFuncSeparatei()`, which gleam doesn't expose.
+type BlendFuncSeparateiFn = unsafe extern "system" fn(
+    buf: GLuint,
+    src_rgb: GLenum,
+    dst_rgb: GLenum,
+    src_alpha: GLenum,
+    dst_alpha: GLenum,
+);
+
+/// `glBlendEquationSeparatei()`, which gleam doesn't expose.
+type BlendEquationSeparateiFn =
+    unsafe extern "system" fn(buf: GLuint, mode_rgb: GLenum, mode_alpha: GLenum);
+
+/// `glColorMaski()`, which gleam doesn't expose.
+type ColorMaskiFn = unsafe extern "system" fn(
+    buf: GLuint,
+    red: GLboolean,
+    green: GLboolean,
+    blue: GLboolean,
+    alpha: GLboolean,
+);
+
+/// The GL 4.0 and GLES 3.2 per-draw-buffer blend functions.
+#[derive(Copy, Clone)]
+struct IndexedBlendFns {
+    enable_i: EnableiFn,
+    disable_i: EnableiFn,
+    blend_func_separate_i: BlendFuncSeparateiFn,
+    blend_equation_separate_i: BlendEquationSeparateiFn,
+    color_mask_i: ColorMaskiFn,
+}
+
+/// The sampler state of an image, which identifies a cached sampler object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct SamplerKey {
+    min_filter: GLenum,
+    mag_filter: ::Filter,
+    wrap_u: ::Wrap,
+    wrap_v: ::Wrap,
+    wrap_w: ::Wrap,
+    max_anisotropy: u32,
+    min_lod_bits: u32,
+    max_lod_bits: u32,
+}
+
+/// `glClearBufferfv()`, which gleam doesn't expose.
+type ClearBufferfvFn =
+    unsafe extern "system" fn(buffer: GLenum, drawbuffer: GLint, value: *const GLfloat);
 
 pub struct Backend {
     in_pass: bool,
     force_gles2: bool,
+    headless: bool,
+    seamless_cubemaps: bool,
     default_framebuffer: GLuint,
     cur_pass_width: usize,
     cur_pass_height: usize,
-    curr_pass: PassResource, // TODO why was this a pointer?
     cur_pass_id: ::Pass,
+    active_frame: usize,
+    frame_fences: Vec<Option<GLsync>>,
     cache: ContextCache,
     features: HashSet<::Feature>,
+    limits: Limits,
     ext_anisotropic: bool,
     max_anisotropy: GLint,
+    gl_polygon_mode: Option<PolygonModeFn>,
+    polygon_mode_warned: bool,
+    depth_bias_clamp_warned: bool,
+    depth_clamp_warned: bool,
+    line_width_range: (f32, f32),
+    gl_clear_buffer_fv: Option<ClearBufferfvFn>,
+    gl_renderbuffer_storage_multisample: Option<RenderbufferStorageMultisampleFn>,
+    gl_primitive_restart_index: Option<PrimitiveRestartIndexFn>,
+    gl_tex_storage_2d: Option<TexStorage2DFn>,
+    gl_tex_storage_3d: Option<TexStorage3DFn>,
+    gl_sampler_fns: Option<SamplerFns>,
+    gl_indexed_blend_fns: Option<IndexedBlendFns>,
+    sampler_cache: HashMap<SamplerKey, GLuint>,
     #[cfg(not(feature = "gles2"))] vao: GLuint,
     gl: std::rc::Rc<Gl>,
 }
@@ -48,22 +210,162 @@ impl Backend {
         let mut res = Backend {
             in_pass: false,
             force_gles2: desc.gl_force_gles2,
-            default_framebuffer: gl.get_integer_v(gl::FRAMEBUFFER_BINDING) as GLuint,
+            headless: desc.headless,
+            seamless_cubemaps: cfg!(feature = "glcore33")
+                && !desc.gl_force_gles2
+                && desc.gl_seamless_cubemaps,
+            default_framebuffer: if desc.headless {
+                0
+            } else {
+                gl.get_integer_v(gl::FRAMEBUFFER_BINDING) as GLuint
+            },
             cur_pass_width: 0,
             cur_pass_height: 0,
-            curr_pass: PassResource::default(),
             cur_pass_id: ::Pass::default(),
+            active_frame: 0,
+            frame_fences: vec![None; ::NUM_INFLIGHT_FRAMES],
             cache: ContextCache::default(),
             features: HashSet::<::Feature>::new(),
+            limits: Limits::default(),
             ext_anisotropic: false,
             max_anisotropy: 0,
+            gl_polygon_mode: None,
+            polygon_mode_warned: false,
+            depth_bias_clamp_warned: false,
+            depth_clamp_warned: false,
+            line_width_range: (1.0, 1.0),
+            gl_clear_buffer_fv: None,
+            gl_renderbuffer_storage_multisample: None,
+            gl_primitive_restart_index: None,
+            gl_tex_storage_2d: None,
+            gl_tex_storage_3d: None,
+            gl_sampler_fns: None,
+            gl_indexed_blend_fns: None,
+            sampler_cache: HashMap::new(),
             #[cfg(not(feature = "gles2"))]
             vao: gl::INVALID_VALUE,
             gl: gl,
         };
 
+        if cfg!(feature = "glcore33") && !desc.gl_force_gles2 {
+            let ptr = desc.load_gl_symbol.lookup("glPolygonMode");
+            if !ptr.is_null() {
+                res.gl_polygon_mode = Some(unsafe {
+                    ::std::mem::transmute::<*const os::raw::c_void, PolygonModeFn>(ptr)
+                });
+            }
+            let ptr = desc.load_gl_symbol.lookup("glPrimitiveRestartIndex");
+            if !ptr.is_null() {
+                res.gl_primitive_restart_index = Some(unsafe {
+                    ::std::mem::transmute::<*const os::raw::c_void, PrimitiveRestartIndexFn>(ptr)
+                });
+            }
+            if desc.gl_use_sampler_objects {
+                let ptrs: Vec<_> = [
+                    "glGenSamplers",
+                    "glDeleteSamplers",
+                    "glBindSampler",
+                    "glSamplerParameteri",
+                    "glSamplerParameterf",
+                ]
+                .iter()
+                .map(|name| desc.load_gl_symbol.lookup(name))
+                .collect();
+                if ptrs.iter().all(|ptr| !ptr.is_null()) {
+                    type Ptr = *const os::raw::c_void;
+                    res.gl_sampler_fns = Some(unsafe {
+                        SamplerFns {
+                            gen_samplers: ::std::mem::transmute::<Ptr, GenSamplersFn>(ptrs[0]),
+                            delete_samplers: ::std::mem::transmute::<Ptr, DeleteSamplersFn>(
+                                ptrs[1],
+                            ),
+                            bind_sampler: ::std::mem::transmute::<Ptr, BindSamplerFn>(ptrs[2]),
+                            sampler_parameter_i: ::std::mem::transmute::<Ptr, SamplerParameteriFn>(
+                                ptrs[3],
+                            ),
+                            sampler_parameter_f: ::std::mem::transmute::<Ptr, SamplerParameterfFn>(
+                                ptrs[4],
+                            ),
+                        }
+                    });
+                } else {
+                    warn!("GL sampler objects are not supported, using texture parameters");
+                }
+            }
+        }
+
+        if !res.is_gles2() {
+            let ptrs: Vec<_> = [
+                "glEnablei",
+                "glDisablei",
+                "glBlendFuncSeparatei",
+                "glBlendEquationSeparatei",
+                "glColorMaski",
+            ]
+            .iter()
+            .map(|name| desc.load_gl_symbol.lookup(name))
+            .collect();
+            if ptrs.iter().all(|ptr| !ptr.is_null()) {
+                type Ptr = *const os::raw::c_void;
+                res.gl_indexed_blend_fns = Some(unsafe {
+                    IndexedBlendFns {
+                        enable_i: ::std::mem::transmute::<Ptr, EnableiFn>(ptrs[0]),
+                        disable_i: ::std::mem::transmute::<Ptr, EnableiFn>(ptrs[1]),
+                        blend_func_separate_i: ::std::mem::transmute::<Ptr, BlendFuncSeparateiFn>(
+                            ptrs[2],
+                        ),
+                        blend_equation_separate_i: ::std::mem::transmute::<
+                            Ptr,
+                            BlendEquationSeparateiFn,
+                        >(ptrs[3]),
+                        color_mask_i: ::std::mem::transmute::<Ptr, ColorMaskiFn>(ptrs[4]),
+                    }
+                });
+            }
+            let ptr = desc.load_gl_symbol.lookup("glClearBufferfv");
+            if !ptr.is_null() {
+                res.gl_clear_buffer_fv = Some(unsafe {
+                    ::std::mem::transmute::<*const os::raw::c_void, ClearBufferfvFn>(ptr)
+                });
+            }
+            let ptr = desc
+                .load_gl_symbol
+                .lookup("glRenderbufferStorageMultisample");
+            if !ptr.is_null() {
+                res.gl_renderbuffer_storage_multisample = Some(unsafe {
+                    ::std::mem::transmute::<*const os::raw::c_void, RenderbufferStorageMultisampleFn>(
+                        ptr,
+                    )
+                });
+            }
+            // Immutable texture storage is core in GLES3 and GL 4.2, and
+            // provided by GL_ARB_texture_storage on older GL versions.
+            let ptr = desc.load_gl_symbol.lookup("glTexStorage2D");
+            if !ptr.is_null() {
+                res.gl_tex_storage_2d = Some(unsafe {
+                    ::std::mem::transmute::<*const os::raw::c_void, TexStorage2DFn>(ptr)
+                });
+            }
+            let ptr = desc.load_gl_symbol.lookup("glTexStorage3D");
+            if !ptr.is_null() {
+                res.gl_tex_storage_3d = Some(unsafe {
+                    ::std::mem::transmute::<*const os::raw::c_void, TexStorage3DFn>(ptr)
+                });
+            }
+        }
+
+        let ptr = desc.load_gl_symbol.lookup("glGetFloatv");
+        if !ptr.is_null() {
+            let get_float_v =
+                unsafe { ::std::mem::transmute::<*const os::raw::c_void, GetFloatvFn>(ptr) };
+            let mut range: [GLfloat; 2] = [1.0, 1.0];
+            unsafe { get_float_v(gl::ALIASED_LINE_WIDTH_RANGE, range.as_mut_ptr()) };
+            res.line_width_range = (range[0], range[1]);
+        }
+
         res.reset_state_cache();
         res.init_gl_features();
+        res.init_gl_limits();
 
         res
     }
@@ -72,6 +374,12 @@ impl Backend {
 
     #[cfg(feature = "gles2")]
     fn init_gl_features(&mut self) {
+        self.init_gles2_features();
+    }
+
+    /// Detect the features of GLES2, which are also used in GLES2 fallback
+    /// mode.
+    fn init_gles2_features(&mut self) {
         self.features.insert(Feature::OriginBottomLeft);
 
         let extensions = self.gl.get_string(gl::EXTENSIONS);
@@ -112,15 +420,12 @@ impl Backend {
 
     #[cfg(feature = "gles3")]
     fn init_gl_features(&mut self) {
+        if self.force_gles2 {
+            self.init_gles2_features();
+            return;
+        }
         self.features.insert(Feature::OriginBottomLeft);
-        self.features.insert(Feature::Instancing);
-        self.features.insert(Feature::TextureHalfFloat);
-        self.features.insert(Feature::TextureFloat);
-        self.features.insert(Feature::MSAARenderTargets);
-        self.features.insert(Feature::PackedVertexFormat_10_2);
-        self.features.insert(Feature::MultipleRenderTarget);
-        self.features.insert(Feature::ImageType3D);
-        self.features.insert(Feature::ImageTypeArray);
+        self.features.extend(GLES3_FEATURES.iter().cloned());
 
         let extensions = self.gl.get_string(gl::EXTENSIONS);
         for extension in extensions.split_whitespace() {
@@ -151,15 +456,12 @@ impl Backend {
 
     #[cfg(feature = "glcore33")]
     fn init_gl_features(&mut self) {
+        if self.force_gles2 {
+            self.init_gles2_features();
+            return;
+        }
         self.features.insert(Feature::OriginBottomLeft);
-        self.features.insert(Feature::Instancing);
-        self.features.insert(Feature::TextureHalfFloat);
-        self.features.insert(Feature::TextureFloat);
-        self.features.insert(Feature::MSAARenderTargets);
-        self.features.insert(Feature::PackedVertexFormat_10_2);
-        self.features.insert(Feature::MultipleRenderTarget);
-        self.features.insert(Feature::ImageType3D);
-        self.features.insert(Feature::ImageTypeArray);
+        self.features.extend(GLES3_FEATURES.iter().cloned());
 
         let num_ext = self.gl.get_integer_v(gl::NUM_EXTENSIONS);
         for i in 0..num_ext {
@@ -177,6 +479,30 @@ impl Backend {
         }
     }
 
+    fn init_gl_limits(&mut self) {
+        let get = |name| self.gl.get_integer_v(name).max(0) as usize;
+        let mut limits = Limits {
+            max_image_size_2d: get(gl::MAX_TEXTURE_SIZE),
+            max_image_size_cube: get(gl::MAX_CUBE_MAP_TEXTURE_SIZE),
+            max_image_size_3d: 0,
+            max_image_array_layers: 0,
+            max_vertex_attributes: get(gl::MAX_VERTEX_ATTRIBS).min(::MAX_VERTEX_ATTRIBUTES),
+            max_uniform_block_size: 0,
+            max_sample_count: 1,
+        };
+        if self.is_gles2() {
+            // Uniform blocks are emulated with plain uniforms, which are
+            // counted in vec4s.
+            limits.max_uniform_block_size = get(gl::MAX_VERTEX_UNIFORM_VECTORS) * 16;
+        } else {
+            limits.max_image_size_3d = get(gl::MAX_3D_TEXTURE_SIZE);
+            limits.max_image_array_layers = get(gl::MAX_ARRAY_TEXTURE_LAYERS);
+            limits.max_uniform_block_size = get(gl::MAX_UNIFORM_BLOCK_SIZE);
+            limits.max_sample_count = get(gl::MAX_SAMPLES).max(1);
+        }
+        self.limits = limits;
+    }
+
     #[cfg(not(feature = "gles2"))]
     fn reset_vao(&mut self) {
         if !self.force_gles2 {
@@ -191,15 +517,617 @@ impl Backend {
     #[cfg(feature = "gles2")]
     fn reset_vao(&mut self) {}
 
+    fn apply_depth_stencil_state(&mut self, new_ds: &::DepthStencilState) {
+        let cache_ds = &mut self.cache.ds;
+        if new_ds.depth_compare_func != cache_ds.depth_compare_func {
+            cache_ds.depth_compare_func = new_ds.depth_compare_func;
+            self.gl
+                .depth_func(new_ds.depth_compare_func.gl_compare_func());
+        }
+        if new_ds.depth_write_enabled != cache_ds.depth_write_enabled {
+            cache_ds.depth_write_enabled = new_ds.depth_write_enabled;
+            self.gl.depth_mask(new_ds.depth_write_enabled);
+        }
+        if new_ds.stencil_enabled != cache_ds.stencil_enabled {
+            cache_ds.stencil_enabled = new_ds.stencil_enabled;
+            if new_ds.stencil_enabled {
+                self.gl.enable(gl::STENCIL_TEST);
+            } else {
+                self.gl.disable(gl::STENCIL_TEST);
+            }
+        }
+        if new_ds.stencil_write_mask != cache_ds.stencil_write_mask {
+            cache_ds.stencil_write_mask = new_ds.stencil_write_mask;
+            self.gl
+                .stencil_mask(GLuint::from(new_ds.stencil_write_mask));
+        }
+        let ref_or_mask_changed = new_ds.stencil_ref != cache_ds.stencil_ref
+            || new_ds.stencil_read_mask != cache_ds.stencil_read_mask;
+        let mut faces = [
+            (
+                gl::FRONT,
+                &new_ds.stencil_front,
+                &mut cache_ds.stencil_front,
+            ),
+            (gl::BACK, &new_ds.stencil_back, &mut cache_ds.stencil_back),
+        ];
+        for &mut (gl_face, new_s, ref mut cache_s) in &mut faces {
+            if ref_or_mask_changed || new_s.compare_func != cache_s.compare_func {
+                cache_s.compare_func = new_s.compare_func;
+                self.gl.stencil_func_separate(
+                    gl_face,
+                    new_s.compare_func.gl_compare_func(),
+                    GLint::from(new_ds.stencil_ref),
+                    GLuint::from(new_ds.stencil_read_mask),
+                );
+            }
+            if new_s.fail_op != cache_s.fail_op
+                || new_s.depth_fail_op != cache_s.depth_fail_op
+                || new_s.pass_op != cache_s.pass_op
+            {
+                cache_s.fail_op = new_s.fail_op;
+                cache_s.depth_fail_op = new_s.depth_fail_op;
+                cache_s.pass_op = new_s.pass_op;
+                self.gl.stencil_op_separate(
+                    gl_face,
+                    new_s.fail_op.gl_stencil_op(),
+                    new_s.depth_fail_op.gl_stencil_op(),
+                    new_s.pass_op.gl_stencil_op(),
+                );
+            }
+        }
+        cache_ds.stencil_ref = new_ds.stencil_ref;
+        cache_ds.stencil_read_mask = new_ds.stencil_read_mask;
+    }
+
+    fn apply_blend_state(&mut self, new_b: &::BlendState) {
+        // Per-attachment blend state leaves the cached state stale.
+        let dirty = ::std::mem::replace(&mut self.cache.blend_indexed, false);
+        let cache_b = &mut self.cache.blend;
+        if dirty || new_b.enabled != cache_b.enabled {
+            cache_b.enabled = new_b.enabled;
+            if new_b.enabled {
+                self.gl.enable(gl::BLEND);
+            } else {
+                self.gl.disable(gl::BLEND);
+            }
+        }
+        if dirty
+            || new_b.src_factor_rgb != cache_b.src_factor_rgb
+            || new_b.dst_factor_rgb != cache_b.dst_factor_rgb
+            || new_b.src_factor_alpha != cache_b.src_factor_alpha
+            || new_b.dst_factor_alpha != cache_b.dst_factor_alpha
+        {
+            cache_b.src_factor_rgb = new_b.src_factor_rgb;
+            cache_b.dst_factor_rgb = new_b.dst_factor_rgb;
+            cache_b.src_factor_alpha = new_b.src_factor_alpha;
+            cache_b.dst_factor_alpha = new_b.dst_factor_alpha;
+            self.gl.blend_func_separate(
+                new_b.src_factor_rgb.gl_blend_factor(),
+                new_b.dst_factor_rgb.gl_blend_factor(),
+                new_b.src_factor_alpha.gl_blend_factor(),
+                new_b.dst_factor_alpha.gl_blend_factor(),
+            );
+        }
+        if dirty || new_b.op_rgb != cache_b.op_rgb || new_b.op_alpha != cache_b.op_alpha {
+            cache_b.op_rgb = new_b.op_rgb;
+            cache_b.op_alpha = new_b.op_alpha;
+            self.gl
+                .blend_equation_separate(new_b.op_rgb.gl_blend_op(), new_b.op_alpha.gl_blend_op());
+        }
+        if dirty || new_b.color_write_mask != cache_b.color_write_mask {
+            cache_b.color_write_mask = new_b.color_write_mask;
+            let mask = new_b.color_write_mask;
+            self.gl.color_mask(
+                mask.contains(::ColorMask::R),
+                mask.contains(::ColorMask::G),
+                mask.contains(::ColorMask::B),
+                mask.contains(::ColorMask::A),
+            );
+        }
+        if new_b.blend_color != cache_b.blend_color {
+            cache_b.blend_color = new_b.blend_color;
+            let c = new_b.blend_color;
+            self.gl.blend_color(c[0], c[1], c[2], c[3]);
+        }
+    }
+
+    /// Override the blend state of the first `count` color attachments,
+    /// after `apply_blend_state()` applied the shared state.
+    fn apply_attachment_blend_states(&mut self, blends: &[::BlendState], count: usize) {
+        let fns = match self.gl_indexed_blend_fns {
+            Some(fns) => fns,
+            None => return,
+        };
+        for (index, b) in blends.iter().take(count).enumerate() {
+            let index = index as GLuint;
+            let mask = b.color_write_mask;
+            #[allow(unsafe_code)]
+            unsafe {
+                if b.enabled {
+                    (fns.enable_i)(gl::BLEND, index);
+                } else {
+                    (fns.disable_i)(gl::BLEND, index);
+                }
+                (fns.blend_func_separate_i)(
+                    index,
+                    b.src_factor_rgb.gl_blend_factor(),
+                    b.dst_factor_rgb.gl_blend_factor(),
+                    b.src_factor_alpha.gl_blend_factor(),
+                    b.dst_factor_alpha.gl_blend_factor(),
+                );
+                (fns.blend_equation_separate_i)(
+                    index,
+                    b.op_rgb.gl_blend_op(),
+                    b.op_alpha.gl_blend_op(),
+                );
+                (fns.color_mask_i)(
+                    index,
+                    mask.contains(::ColorMask::R) as GLboolean,
+                    mask.contains(::ColorMask::G) as GLboolean,
+                    mask.contains(::ColorMask::B) as GLboolean,
+                    mask.contains(::ColorMask::A) as GLboolean,
+                );
+            }
+        }
+        self.cache.blend_indexed = true;
+    }
+
+    fn apply_rasterizer_state(&mut self, new_r: &::RasterizerState) {
+        let cache_r = &mut self.cache.rast;
+        if new_r.cull_mode != cache_r.cull_mode {
+            cache_r.cull_mode = new_r.cull_mode;
+            if new_r.cull_mode == ::CullMode::None {
+                self.gl.disable(gl::CULL_FACE);
+            } else {
+                self.gl.enable(gl::CULL_FACE);
+                self.gl.cull_face(new_r.cull_mode.gl_cull_face());
+            }
+        }
+        if new_r.face_winding != cache_r.face_winding {
+            cache_r.face_winding = new_r.face_winding;
+            self.gl.front_face(new_r.face_winding.gl_front_face());
+        }
+        if new_r.polygon_mode != cache_r.polygon_mode {
+            match self.gl_polygon_mode {
+                Some(polygon_mode) => {
+                    cache_r.polygon_mode = new_r.polygon_mode;
+                    let mode = new_r.polygon_mode.gl_polygon_mode();
+                    #[allow(unsafe_code)]
+                    unsafe {
+                        polygon_mode(gl::FRONT_AND_BACK, mode)
+                    };
+                }
+                None => {
+                    if !self.polygon_mode_warned {
+                        self.polygon_mode_warned = true;
+                        warn!(
+                            "glPolygonMode is unavailable, ignoring polygon mode {:?}",
+                            new_r.polygon_mode
+                        );
+                    }
+                }
+            }
+        }
+        if new_r.alpha_to_coverage_enabled != cache_r.alpha_to_coverage_enabled {
+            cache_r.alpha_to_coverage_enabled = new_r.alpha_to_coverage_enabled;
+            if new_r.alpha_to_coverage_enabled {
+                self.gl.enable(gl::SAMPLE_ALPHA_TO_COVERAGE);
+            } else {
+                self.gl.disable(gl::SAMPLE_ALPHA_TO_COVERAGE);
+            }
+        }
+        if new_r.depth_bias != cache_r.depth_bias
+            || new_r.depth_bias_slope_scale != cache_r.depth_bias_slope_scale
+        {
+            cache_r.depth_bias = new_r.depth_bias;
+            cache_r.depth_bias_slope_scale = new_r.depth_bias_slope_scale;
+            self.gl
+                .polygon_offset(new_r.depth_bias_slope_scale, new_r.depth_bias);
+            let po_enabled = new_r.depth_bias != 0.0 || new_r.depth_bias_slope_scale != 0.0;
+            if po_enabled != self.cache.polygon_offset_enabled {
+                self.cache.polygon_offset_enabled = po_enabled;
+                if po_enabled {
+                    self.gl.enable(gl::POLYGON_OFFSET_FILL);
+                } else {
+                    self.gl.disable(gl::POLYGON_OFFSET_FILL);
+                }
+            }
+        }
+        if new_r.depth_bias_clamp != 0.0 && !self.depth_bias_clamp_warned {
+            // glPolygonOffsetClamp is not part of core GL or GLES.
+            self.depth_bias_clamp_warned = true;
+            warn!(
+                "depth bias clamp is not supported by the GL backend, ignoring {}",
+                new_r.depth_bias_clamp
+            );
+        }
+        if new_r.depth_clamp != cache_r.depth_clamp {
+            if cfg!(feature = "glcore33") && !self.force_gles2 {
+                cache_r.depth_clamp = new_r.depth_clamp;
+                if new_r.depth_clamp {
+                    self.gl.enable(GL_DEPTH_CLAMP);
+                } else {
+                    self.gl.disable(GL_DEPTH_CLAMP);
+                }
+            } else if !self.depth_clamp_warned {
+                self.depth_clamp_warned = true;
+                warn!("depth clamp is not supported by GLES, ignoring it");
+            }
+        }
+        if cfg!(feature = "glcore33") && new_r.sample_count != cache_r.sample_count {
+            cache_r.sample_count = new_r.sample_count;
+            if new_r.sample_count > 1 {
+                self.gl.enable(gl::MULTISAMPLE);
+            } else {
+                self.gl.disable(gl::MULTISAMPLE);
+            }
+        }
+    }
+
+    fn apply_line_width(&mut self, line_width: f32) {
+        let line_width = clamp_line_width(line_width, self.line_width_range);
+        if line_width != self.cache.rast.line_width {
+            self.cache.rast.line_width = line_width;
+            self.gl.line_width(line_width);
+        }
+    }
+
+    fn apply_vertex_attributes(&mut self, gl_attrs: &[GlAttr], vbs: &[&BufferResource]) {
+        let instancing = self.query_feature(Feature::Instancing);
+        let mut gl_vb = 0;
+        for attr_index in 0..::MAX_VERTEX_ATTRIBUTES {
+            let attr = gl_attrs.get(attr_index).cloned().unwrap_or_default();
+            let cache_attr = &mut self.cache.attrs[attr_index];
+            let mut cache_attr_dirty = false;
+            if attr.vb_index >= 0 {
+                // The attribute is enabled.
+                let vb = vbs[attr.vb_index as usize];
+                let vb_buf = vb.gl_buf[vb.active_slot];
+                let cached = &cache_attr.gl_attr;
+                if vb_buf != cache_attr.gl_vbuf
+                    || attr.size != cached.size
+                    || attr.attr_type != cached.attr_type
+                    || attr.normalized != cached.normalized
+                    || attr.stride != cached.stride
+                    || attr.offset != cached.offset
+                {
+                    if gl_vb != vb_buf {
+                        gl_vb = vb_buf;
+                        self.gl.bind_buffer(gl::ARRAY_BUFFER, gl_vb);
+                    }
+                    self.gl.vertex_attrib_pointer(
+                        attr_index as GLuint,
+                        GLint::from(attr.size),
+                        attr.attr_type,
+                        attr.normalized != 0,
+                        attr.stride,
+                        attr.offset,
+                    );
+                    cache_attr.gl_vbuf = vb_buf;
+                    cache_attr_dirty = true;
+                }
+                if cache_attr.gl_attr.vb_index == -1 {
+                    self.gl.enable_vertex_attrib_array(attr_index as GLuint);
+                    cache_attr_dirty = true;
+                }
+                if instancing && cache_attr.gl_attr.divisor != attr.divisor {
+                    self.gl
+                        .vertex_attrib_divisor(attr_index as GLuint, attr.divisor as GLuint);
+                    cache_attr_dirty = true;
+                }
+            } else if cache_attr.gl_attr.vb_index != -1 {
+                // The attribute is disabled.
+                self.gl.disable_vertex_attrib_array(attr_index as GLuint);
+                cache_attr_dirty = true;
+            }
+            if cache_attr_dirty {
+                cache_attr.gl_attr = attr;
+            }
+        }
+    }
+
+    fn is_gles2(&self) -> bool {
+        cfg!(feature = "gles2") || self.force_gles2
+    }
+
+    /// The capability that enables primitive restart, or `None` if it isn't
+    /// supported.
+    ///
+    /// GLES3 restarts at the maximum value of the index type, desktop GL
+    /// restarts at an index set with `glPrimitiveRestartIndex()`.
+    fn primitive_restart_cap(&self) -> Option<GLenum> {
+        if self.is_gles2() {
+            None
+        } else if self.gl_primitive_restart_index.is_some() {
+            Some(GL_PRIMITIVE_RESTART)
+        } else if cfg!(feature = "gles3") {
+            Some(GL_PRIMITIVE_RESTART_FIXED_INDEX)
+        } else {
+            None
+        }
+    }
+
+    fn apply_primitive_restart(&mut self, enabled: bool, index_type: Option<::IndexType>) {
+        let cap = match self.primitive_restart_cap() {
+            Some(cap) => cap,
+            None => return,
+        };
+        if enabled != self.cache.primitive_restart {
+            self.cache.primitive_restart = enabled;
+            if enabled {
+                self.gl.enable(cap);
+            } else {
+                self.gl.disable(cap);
+            }
+        }
+        if let (true, Some(restart_index)) = (enabled, self.gl_primitive_restart_index) {
+            let index = match index_type {
+                Some(::IndexType::UInt32) => 0xFFFF_FFFF,
+                _ => 0xFFFF,
+            };
+            if self.cache.primitive_restart_index != index {
+                self.cache.primitive_restart_index = index;
+                #[allow(unsafe_code)]
+                unsafe {
+                    restart_index(index)
+                };
+            }
+        }
+    }
+
+    /// Clear the buffers of the current framebuffer selected by `clear_mask`,
+    /// using the depth and stencil clear values of `action`.
+    fn clear_buffers(&mut self, action: &PassAction, clear_mask: GLbitfield) {
+        if clear_mask & gl::DEPTH_BUFFER_BIT != 0 {
+            self.gl.clear_depth(f64::from(action.depth.val));
+        }
+        if clear_mask & gl::STENCIL_BUFFER_BIT != 0 {
+            self.gl.clear_stencil(GLint::from(action.stencil.val));
+        }
+        if clear_mask != 0 {
+            self.gl.clear(clear_mask);
+        }
+    }
+
+    fn bind_texture(&mut self, slot: usize, target: GLenum, tex: GLuint) {
+        if self.cache.textures[slot] != (target, tex) {
+            self.cache.textures[slot] = (target, tex);
+            self.gl.active_texture(gl::TEXTURE0 + slot as GLenum);
+            self.gl.bind_texture(target, tex);
+        }
+    }
+
+    fn bind_sampler(&mut self, unit: usize, sampler: GLuint) {
+        if let Some(fns) = self.gl_sampler_fns {
+            if self.cache.samplers[unit] != sampler {
+                self.cache.samplers[unit] = sampler;
+                #[allow(unsafe_code)]
+                unsafe {
+                    (fns.bind_sampler)(unit as GLuint, sampler)
+                };
+            }
+        }
+    }
+
+    /// Get the shared sampler object for the sampler state of an image,
+    /// creating it if it isn't cached yet.
+    ///
+    /// Returns 0 if sampler objects aren't used.
+    fn cached_sampler(&mut self, img: &ImageResource, min_lod: f32, max_lod: f32) -> GLuint {
+        let fns = match self.gl_sampler_fns {
+            Some(fns) => fns,
+            None => return 0,
+        };
+        let key = sampler_key(img, min_lod, max_lod);
+        if let Some(&sampler) = self.sampler_cache.get(&key) {
+            return sampler;
+        }
+
+        let mut sampler = 0;
+        let params = [
+            (gl::TEXTURE_MIN_FILTER, key.min_filter),
+            (gl::TEXTURE_MAG_FILTER, key.mag_filter.gl_filter()),
+            (gl::TEXTURE_WRAP_S, key.wrap_u.gl_wrap()),
+            (gl::TEXTURE_WRAP_T, key.wrap_v.gl_wrap()),
+            (gl::TEXTURE_WRAP_R, key.wrap_w.gl_wrap()),
+        ];
+        let max_aniso = if self.ext_anisotropic && key.max_anisotropy > 1 {
+            Some((key.max_anisotropy as GLint).min(self.max_anisotropy))
+        } else {
+            None
+        };
+        #[allow(unsafe_code)]
+        unsafe {
+            (fns.gen_samplers)(1, &mut sampler);
+            for &(pname, param) in &params {
+                (fns.sampler_parameter_i)(sampler, pname, param as GLint);
+            }
+            if let Some(max_aniso) = max_aniso {
+                (fns.sampler_parameter_i)(sampler, GL_TEXTURE_MAX_ANISOTROPY_EXT, max_aniso);
+            }
+            (fns.sampler_parameter_f)(sampler, gl::TEXTURE_MIN_LOD, min_lod);
+            (fns.sampler_parameter_f)(sampler, gl::TEXTURE_MAX_LOD, max_lod);
+        }
+        self.sampler_cache.insert(key, sampler);
+        sampler
+    }
+
+    /// Delete the cached sampler objects.
+    pub fn destroy_sampler_cache(&mut self) {
+        if let Some(fns) = self.gl_sampler_fns {
+            for unit in 0..self.cache.samplers.len() {
+                self.bind_sampler(unit, 0);
+            }
+            let samplers: Vec<GLuint> = self.sampler_cache.drain().map(|(_, s)| s).collect();
+            if !samplers.is_empty() {
+                #[allow(unsafe_code)]
+                unsafe {
+                    (fns.delete_samplers)(samplers.len() as GLsizei, samplers.as_ptr())
+                };
+            }
+        }
+    }
+
+    fn reset_texture_bindings(&mut self) {
+        for slot in 0..self.cache.textures.len() {
+            let (target, tex) = self.cache.textures[slot];
+            if tex != 0 {
+                self.gl.active_texture(gl::TEXTURE0 + slot as GLenum);
+                self.gl.bind_texture(target, 0);
+                self.cache.textures[slot] = (target, 0);
+            }
+        }
+    }
+
+    fn is_supported_pixel_format(&self, fmt: PixelFormat) -> bool {
+        match fmt {
+            PixelFormat::DXT1 | PixelFormat::DXT3 | PixelFormat::DXT5 => {
+                self.query_feature(Feature::TextureCompressionDXT)
+            }
+            PixelFormat::PVRTC2_RGB
+            | PixelFormat::PVRTC4_RGB
+            | PixelFormat::PVRTC2_RGBA
+            | PixelFormat::PVRTC4_RGBA => self.query_feature(Feature::TextureCompressionPVRTC),
+            PixelFormat::ETC2_RGB8 | PixelFormat::ETC2_SRGB8 => {
+                self.query_feature(Feature::TextureCompressionETC2)
+            }
+            PixelFormat::RGBA32F | PixelFormat::R32F => self.query_feature(Feature::TextureFloat),
+            PixelFormat::RGBA16F | PixelFormat::R16F => {
+                self.query_feature(Feature::TextureHalfFloat)
+            }
+            // GLES2 has no single and two channel formats besides L8.
+            PixelFormat::R8 | PixelFormat::RG8 => !self.is_gles2(),
+            // GLES2 has no packed float formats.
+            PixelFormat::R11G11B10F | PixelFormat::RGB9E5 => !self.is_gles2(),
+            // GLES only has BGRA textures through an extension.
+            PixelFormat::BGRA8 => cfg!(feature = "glcore33") && !self.is_gles2(),
+            _ => true,
+        }
+    }
+
     /* Public interface methods */
 
     pub fn query_feature(&self, feature: Feature) -> bool {
         self.features.contains(&feature)
     }
 
+    pub fn query_feature_detail(&self, feature: Feature) -> FeatureSupport {
+        if self.features.contains(&feature) {
+            FeatureSupport::Supported
+        } else if self.force_gles2 && GLES3_FEATURES.contains(&feature) {
+            FeatureSupport::DisabledByGles2Fallback
+        } else if let Some(extension) = self.feature_extension(feature) {
+            FeatureSupport::MissingExtension(extension)
+        } else {
+            FeatureSupport::UnsupportedByBackend
+        }
+    }
+
+    /// The extension that provides a feature, if the feature depends on an
+    /// extension in the current GL flavor.
+    fn feature_extension(&self, feature: Feature) -> Option<&'static str> {
+        let checks_mobile_extensions = self.is_gles2() || cfg!(feature = "gles3");
+        match feature {
+            Feature::Instancing if self.is_gles2() => Some("GL_ANGLE_instanced_arrays"),
+            Feature::TextureFloat if self.is_gles2() => Some("GL_OES_texture_float"),
+            Feature::TextureHalfFloat if self.is_gles2() => Some("GL_OES_texture_half_float"),
+            Feature::TextureCompressionDXT => Some("GL_EXT_texture_compression_s3tc"),
+            Feature::TextureCompressionPVRTC if checks_mobile_extensions => {
+                Some("GL_IMG_texture_compression_pvrtc")
+            }
+            Feature::TextureCompressionATC if checks_mobile_extensions => {
+                Some("GL_AMD_compressed_ATC_texture")
+            }
+            _ => None,
+        }
+    }
+
+    pub fn query_limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn query_pixelformat(&self, fmt: PixelFormat) -> PixelFormatInfo {
+        if !self.is_supported_pixel_format(fmt) {
+            return PixelFormatInfo::default();
+        }
+        let msaa = self.query_feature(Feature::MSAARenderTargets);
+        if fmt.is_valid_rendertarget_depth_format() {
+            // Depth images are created as renderbuffers, which can't be
+            // sampled.
+            return PixelFormatInfo {
+                render: true,
+                msaa,
+                ..PixelFormatInfo::default()
+            };
+        }
+        // Linear filtering and blending of 32-bit float formats need
+        // extensions that are optional on GLES.
+        let is_float32 = fmt == PixelFormat::RGBA32F || fmt == PixelFormat::R32F;
+        let float32_ok = cfg!(feature = "glcore33") && !self.is_gles2();
+        let render = fmt.is_valid_rendertarget_color_format();
+        PixelFormatInfo {
+            sample: true,
+            filter: !is_float32 || float32_ok,
+            render,
+            blend: render && (!is_float32 || float32_ok),
+            msaa: render && msaa,
+        }
+    }
+
+    pub fn query_backend_info(&self) -> BackendInfo {
+        let name = if self.is_gles2() {
+            "GLES2"
+        } else if cfg!(feature = "gles3") {
+            "GLES3"
+        } else {
+            "GLCORE33"
+        };
+        BackendInfo {
+            name,
+            version: self.gl.get_string(gl::VERSION),
+            renderer: self.gl.get_string(gl::RENDERER),
+            force_gles2: self.force_gles2,
+        }
+    }
+
+    pub fn query_line_width_range(&self) -> (f32, f32) {
+        self.line_width_range
+    }
+
+    pub fn on_context_lost(&mut self) {
+        self.in_pass = false;
+        self.sampler_cache.clear();
+        for fence in &mut self.frame_fences {
+            *fence = None;
+        }
+        #[cfg(not(feature = "gles2"))]
+        {
+            self.vao = gl::INVALID_VALUE;
+        }
+    }
+
+    pub fn recreate(&mut self) {
+        if !self.headless {
+            self.default_framebuffer = self.gl.get_integer_v(gl::FRAMEBUFFER_BINDING) as GLuint;
+        }
+        self.features.clear();
+        self.init_gl_features();
+        self.init_gl_limits();
+        self.reset_state_cache();
+    }
+
     pub fn reset_state_cache(&mut self) {
         self.reset_vao();
         self.cache = ContextCache::default();
+        if let Some(fns) = self.gl_sampler_fns {
+            for unit in 0..self.cache.samplers.len() {
+                #[allow(unsafe_code)]
+                unsafe {
+                    (fns.bind_sampler)(unit as GLuint, 0)
+                };
+            }
+        }
 
         self.gl.bind_buffer(gl::ARRAY_BUFFER, 0);
         self.gl.bind_buffer(gl::ELEMENT_ARRAY_BUFFER, 0);
@@ -234,24 +1162,130 @@ impl Backend {
         self.gl.disable(gl::SAMPLE_ALPHA_TO_COVERAGE);
         self.gl.enable(gl::DITHER);
         self.gl.disable(gl::POLYGON_OFFSET_FILL);
+        self.gl.line_width(1.0);
+        if let Some(polygon_mode) = self.gl_polygon_mode {
+            #[allow(unsafe_code)]
+            unsafe {
+                polygon_mode(gl::FRONT_AND_BACK, gl::FILL)
+            };
+        }
+        if let Some(cap) = self.primitive_restart_cap() {
+            self.gl.disable(cap);
+        }
 
         if cfg!(feature = "glcore33") {
             self.gl.enable(gl::MULTISAMPLE);
             self.gl.enable(gl::PROGRAM_POINT_SIZE);
+            self.gl.disable(GL_DEPTH_CLAMP);
+            if self.seamless_cubemaps {
+                self.gl.enable(GL_TEXTURE_CUBE_MAP_SEAMLESS);
+            }
         }
     }
 
-    pub fn apply_viewport(
+    pub fn begin_pass(
         &mut self,
-        x: u32,
-        y: u32,
+        pass: Option<&PassResource>,
+        action: &PassAction,
         width: u32,
         height: u32,
-        origin_top_left: bool,
     ) {
+        self.in_pass = true;
+        self.cur_pass_id = pass.map_or(::Pass::default(), |p| ::Pass { id: p.slot.id });
+        self.cur_pass_width = width as usize;
+        self.cur_pass_height = height as usize;
+
+        let num_color_atts = pass.map_or(1, |p| p.color_atts.len());
+        // Depth and stencil clears are skipped for offscreen passes without
+        // those buffers.
+        let clear_mask = match pass {
+            Some(pass) => {
+                let has_depth = pass.ds_att.image_id.is_valid();
+                let has_stencil =
+                    has_depth && pass.ds_att.image.pixel_format.is_depth_stencil_format();
+                clear_mask(action, num_color_atts > 0, has_depth, has_stencil)
+            }
+            None => clear_mask(action, true, true, true),
+        };
+        match pass {
+            Some(pass) => {
+                self.gl.bind_framebuffer(gl::FRAMEBUFFER, pass.gl_fb);
+                if self.is_gles2() {
+                    // GLES2 has no draw buffers.
+                } else if num_color_atts == 0 {
+                    self.gl.draw_buffers(&[gl::NONE]);
+                } else {
+                    self.gl.draw_buffers(&COLOR_ATTACHMENTS[..num_color_atts]);
+                }
+            }
+            None => {
+                self.gl
+                    .bind_framebuffer(gl::FRAMEBUFFER, self.default_framebuffer);
+            }
+        }
+        self.gl.viewport(0, 0, width as GLint, height as GLint);
+        self.gl.scissor(0, 0, width as GLint, height as GLint);
+
+        // Clearing is affected by the color, depth and stencil write masks,
+        // so open them up where needed.
+        let mut need_pip_cache_flush = false;
+        let clears_color = action.colors[..num_color_atts]
+            .iter()
+            .any(|color| color.action == Action::Clear);
+        if clears_color && self.cache.blend.color_write_mask != ::ColorMask::RGBA {
+            need_pip_cache_flush = true;
+            self.cache.blend.color_write_mask = ::ColorMask::RGBA;
+            self.gl.color_mask(true, true, true, true);
+        }
+        if clear_mask & gl::DEPTH_BUFFER_BIT != 0 {
+            if !self.cache.ds.depth_write_enabled {
+                need_pip_cache_flush = true;
+                self.cache.ds.depth_write_enabled = true;
+                self.gl.depth_mask(true);
+            }
+            if self.cache.ds.depth_compare_func != ::CompareFunc::Always {
+                need_pip_cache_flush = true;
+                self.cache.ds.depth_compare_func = ::CompareFunc::Always;
+                self.gl.depth_func(gl::ALWAYS);
+            }
+        }
+        if clear_mask & gl::STENCIL_BUFFER_BIT != 0 && self.cache.ds.stencil_write_mask != 0xFF {
+            need_pip_cache_flush = true;
+            self.cache.ds.stencil_write_mask = 0xFF;
+            self.gl.stencil_mask(0xFF);
+        }
+        if need_pip_cache_flush {
+            // The state cache was modified directly, so force the next
+            // apply_draw_state() to re-apply the pipeline state.
+            self.cache.cur_pipeline_id = ::Pipeline::default();
+        }
+
+        let mrt_clear = pass.and(self.gl_clear_buffer_fv);
+        if let Some(clear_buffer_fv) = mrt_clear {
+            // Each color attachment has its own action, so only clear those
+            // that ask for it and leave the others untouched.
+            for (i, color) in action.colors[..num_color_atts].iter().enumerate() {
+                if color.action == Action::Clear {
+                    clear_color_attachment(clear_buffer_fv, i, &color.val);
+                }
+            }
+            self.clear_buffers(action, clear_mask & !gl::COLOR_BUFFER_BIT);
+        } else {
+            if clear_mask & gl::COLOR_BUFFER_BIT != 0 {
+                let c = action.colors[0].val;
+                self.gl.clear_color(c[0], c[1], c[2], c[3]);
+            }
+            self.clear_buffers(action, clear_mask);
+        }
+    }
+
+    pub fn apply_viewport(&mut self, x: u32, y: u32, width: u32, height: u32, flip: bool) {
         assert!(self.in_pass);
-        let y = if origin_top_left {
-            self.cur_pass_height as u32 - (y + height)
+        let pass_width = self.cur_pass_width as u32;
+        let pass_height = self.cur_pass_height as u32;
+        let (x, y, width, height) = clamp_rect(pass_width, pass_height, x, y, width, height);
+        let y = if flip {
+            flip_y(pass_height, y, height)
         } else {
             y
         };
@@ -259,17 +1293,13 @@ impl Backend {
             .viewport(x as i32, y as i32, width as i32, height as i32);
     }
 
-    pub fn apply_scissor_rect(
-        &mut self,
-        x: u32,
-        y: u32,
-        width: u32,
-        height: u32,
-        origin_top_left: bool,
-    ) {
+    pub fn apply_scissor_rect(&mut self, x: u32, y: u32, width: u32, height: u32, flip: bool) {
         assert!(self.in_pass);
-        let y = if origin_top_left {
-            self.cur_pass_height as u32 - (y + height)
+        let pass_width = self.cur_pass_width as u32;
+        let pass_height = self.cur_pass_height as u32;
+        let (x, y, width, height) = clamp_rect(pass_width, pass_height, x, y, width, height);
+        let y = if flip {
+            flip_y(pass_height, y, height)
         } else {
             y
         };
@@ -277,68 +1307,1446 @@ impl Backend {
             .scissor(x as i32, y as i32, width as i32, height as i32);
     }
 
-    pub fn apply_uniform_block(
-        &mut self,
-        stage: ShaderStage,
-        ub_index: u32,
-        data: *const os::raw::c_void,
-        num_bytes: u32,
-    ) {
-        unimplemented!();
+    pub fn disable_scissor(&mut self) {
+        assert!(self.in_pass);
+        self.gl.scissor(
+            0,
+            0,
+            self.cur_pass_width as GLint,
+            self.cur_pass_height as GLint,
+        );
     }
 
-    pub fn draw(&mut self, base_element: u32, num_elements: u32, num_instances: u32) {
-        let i_type: GLenum = self.cache.cur_index_type;
-        let p_type: GLenum = self.cache.cur_primitive_type;
-
-        // OpenGL expects these to be signed integers.
-        let num_elements = num_elements as GLint;
-        let num_instances = num_instances as GLint;
-
-        if 0 != i_type {
-            /* indexed rendering */
-            let i_size = if i_type == gl::UNSIGNED_SHORT { 2 } else { 4 };
-            let indices = base_element * i_size;
-            if num_instances == 1 {
-                self.gl.draw_elements(p_type, num_elements, i_type, indices);
-            } else {
-                if self.query_feature(Feature::Instancing) {
-                    self.gl.draw_elements_instanced(
-                        p_type,
-                        num_elements,
-                        i_type,
-                        indices,
-                        num_instances,
-                    );
-                }
-            }
-        } else {
-            /* non-indexed rendering */
-            if num_instances == 1 {
-                self.gl
-                    .draw_arrays(p_type, base_element as GLint, num_elements);
-            } else {
-                if self.query_feature(Feature::Instancing) {
-                    self.gl.draw_arrays_instanced(
-                        p_type,
-                        base_element as GLint,
-                        num_elements,
-                        num_instances,
-                    );
-                }
-            }
+    pub fn read_pixels(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        out: &mut [u8],
+    ) -> Result<(), ResourceError> {
+        if !self.in_pass {
+            return Err(ResourceError::Unsupported);
+        }
+        let pass_width = self.cur_pass_width as u32;
+        let pass_height = self.cur_pass_height as u32;
+        if x > pass_width || y > pass_height || width > pass_width - x || height > pass_height - y {
+            return Err(ResourceError::OutOfBounds);
+        }
+        // gleam only knows the byte size of plain 8-bit and 32-bit float
+        // channels, and panics on anything else.
+        let gl_format = format.gl_teximage_format();
+        let gl_type = format.gl_teximage_type();
+        if gl_format == gl::RG || (gl_type != gl::UNSIGNED_BYTE && gl_type != gl::FLOAT) {
+            return Err(ResourceError::Unsupported);
+        }
+        // Convert the top-left origin of the request into the GL framebuffer
+        // origin; the bounds check above keeps the clamp from changing the rect.
+        let (x, y, width, height) = clamp_rect(pass_width, pass_height, x, y, width, height);
+        let y = flip_y(pass_height, y, height);
+        self.gl.pixel_store_i(gl::PACK_ALIGNMENT, 1);
+        let pixels = self.gl.read_pixels(
+            x as i32,
+            y as i32,
+            width as i32,
+            height as i32,
+            gl_format,
+            gl_type,
+        );
+        if pixels.len() != out.len() {
+            return Err(ResourceError::OutOfBounds);
+        }
+        out.copy_from_slice(&pixels);
+        Ok(())
+    }
+
+    #[allow(unsafe_code)]
+    pub fn get_buffer_data(
+        &mut self,
+        buf: &BufferResource,
+        offset: usize,
+        out: &mut [u8],
+    ) -> Result<(), ResourceError> {
+        if buf.usage == Usage::Immutable {
+            return Err(ResourceError::Immutable);
+        }
+        if cfg!(feature = "gles2") || self.force_gles2 {
+            // GLES2 has no way to map a buffer for reading.
+            return Err(ResourceError::Unsupported);
+        }
+        // Use the copy-read target so that the cached vertex and index
+        // buffer bindings stay untouched.
+        self.gl
+            .bind_buffer(gl::COPY_READ_BUFFER, buf.gl_buf[buf.active_slot]);
+        let data = self.gl.map_buffer_range(
+            gl::COPY_READ_BUFFER,
+            offset as GLintptr,
+            out.len() as GLsizeiptr,
+            gl::MAP_READ_BIT,
+        );
+        let res = if data.is_null() {
+            Err(ResourceError::Unsupported)
+        } else {
+            unsafe {
+                ptr::copy_nonoverlapping(data as *const u8, out.as_mut_ptr(), out.len());
+            }
+            Ok(())
+        };
+        self.gl.unmap_buffer(gl::COPY_READ_BUFFER);
+        self.gl.bind_buffer(gl::COPY_READ_BUFFER, 0);
+        res
+    }
+
+    pub fn create_buffer(&mut self, desc: &BufferDesc) -> Option<BufferResource> {
+        let mut buf = BufferResource {
+            size: desc.size,
+            buffer_type: desc.buffer_type,
+            usage: desc.usage,
+            ext_buffers: desc.gl_buffers[0] != 0,
+            ..Default::default()
+        };
+        let num_slots = if desc.usage == Usage::Immutable {
+            1
+        } else {
+            ::NUM_INFLIGHT_FRAMES
+        };
+
+        let target = desc.buffer_type.gl_buffer_target();
+        for slot in 0..num_slots {
+            if buf.ext_buffers {
+                // Inject externally created GL buffers.
+                debug_assert!(desc.gl_buffers[slot] != 0);
+                buf.gl_buf.push(desc.gl_buffers[slot]);
+                continue;
+            }
+            let gl_buf = self.gl.gen_buffers(1)[0];
+            self.gl.bind_buffer(target, gl_buf);
+            self.gl.buffer_data_untyped(
+                target,
+                desc.size as GLsizeiptr,
+                ptr::null(),
+                desc.usage.gl_usage(),
+            );
+            if !desc.content.is_empty() {
+                gl::buffer_sub_data(&*self.gl, target, 0, &desc.content);
+            }
+            buf.gl_buf.push(gl_buf);
+        }
+        if !buf.ext_buffers {
+            self.gl.bind_buffer(target, 0);
+            if target == gl::ELEMENT_ARRAY_BUFFER {
+                self.cache.cur_gl_ib = 0;
+            }
+        }
+        Some(buf)
+    }
+
+    pub fn destroy_buffer(&mut self, buf: BufferResource) {
+        // The GL objects of resources lost with their context are gone.
+        if buf.slot.state != ResourceState::Valid {
+            return;
+        }
+        if !buf.ext_buffers {
+            self.gl.delete_buffers(&buf.gl_buf);
+        }
+    }
+
+    pub fn create_image(&mut self, desc: &ImageDesc) -> Option<ImageResource> {
+        if !self.is_supported_pixel_format(desc.pixel_format) {
+            warn!(
+                "create_image: pixel format {:?} not supported by GL context",
+                desc.pixel_format
+            );
+            return None;
+        }
+        let is_layered = match desc.image_type {
+            ImageType::Texture3D => {
+                if !self.query_feature(Feature::ImageType3D) {
+                    warn!("create_image: 3D textures not supported by GL context");
+                    return None;
+                }
+                true
+            }
+            ImageType::Array => {
+                if !self.query_feature(Feature::ImageTypeArray) {
+                    warn!("create_image: array textures not supported by GL context");
+                    return None;
+                }
+                true
+            }
+            _ => false,
+        };
+        if is_layered {
+            if desc.pixel_format.is_compressed_pixel_format() {
+                warn!(
+                    "create_image: compressed {:?} textures not supported by GL backend",
+                    desc.image_type
+                );
+                return None;
+            }
+            // All depth slices or array layers of a mipmap level are stored
+            // back to back in the subimage content of face 0.
+            for mip in 0..desc.num_mipmaps.max(1) {
+                let content = &desc.content.subimage[mip][0].content;
+                let expected = mip_slice_size(desc.pixel_format, desc.width, desc.height, mip)
+                    * mip_depth(desc.image_type, desc.depth_or_layers as usize, mip);
+                if !content.is_empty() && content.len() != expected {
+                    warn!(
+                        "create_image: mipmap {} of {:?} texture has {} bytes, expected {}",
+                        mip,
+                        desc.image_type,
+                        content.len(),
+                        expected
+                    );
+                    return None;
+                }
+            }
+        }
+
+        let mut img = ImageResource {
+            image_type: desc.image_type,
+            render_target: desc.render_target,
+            width: desc.width,
+            height: desc.height,
+            depth: desc.depth_or_layers as usize,
+            num_mipmaps: desc.num_mipmaps.max(1),
+            usage: desc.usage,
+            pixel_format: desc.pixel_format,
+            sample_count: desc.sample_count.max(1),
+            min_filter: desc.min_filter,
+            mag_filter: desc.mag_filter,
+            wrap_u: desc.wrap_u,
+            wrap_v: desc.wrap_v,
+            wrap_w: desc.wrap_w,
+            ..Default::default()
+        };
+        // GLES2 can only sample non-power-of-two textures with ClampToEdge
+        // wrapping, so coerce the wrap modes rather than rejecting the image.
+        let is_pot = img.width.is_power_of_two() && img.height.is_power_of_two();
+        let repeats = img.wrap_u != ::Wrap::ClampToEdge || img.wrap_v != ::Wrap::ClampToEdge;
+        if self.is_gles2() && !is_pot && repeats {
+            warn!(
+                "create_image: GLES2 only supports ClampToEdge wrapping for {}x{} texture",
+                img.width, img.height
+            );
+            img.wrap_u = ::Wrap::ClampToEdge;
+            img.wrap_v = ::Wrap::ClampToEdge;
+        }
+        img.max_anisotropy = desc.max_anisotropy;
+        img.gl_target = desc.image_type.gl_texture_target();
+        img.num_slots = if desc.usage == Usage::Immutable {
+            1
+        } else {
+            ::NUM_INFLIGHT_FRAMES
+        };
+        img.ext_textures = desc.gl_textures[0] != 0;
+
+        let width = img.width as GLsizei;
+        let height = img.height as GLsizei;
+
+        // Depth formats are rendered into a renderbuffer, not a texture.
+        if img.pixel_format.is_valid_rendertarget_depth_format() {
+            let rb = self.gl.gen_renderbuffers(1)[0];
+            self.gl.bind_renderbuffer(gl::RENDERBUFFER, rb);
+            let format = img.pixel_format.gl_depth_attachment_format();
+            match self.gl_renderbuffer_storage_multisample {
+                Some(storage) if img.sample_count > 1 => {
+                    let samples = img.sample_count as GLsizei;
+                    #[allow(unsafe_code)]
+                    unsafe {
+                        storage(gl::RENDERBUFFER, samples, format, width, height)
+                    };
+                }
+                _ => self
+                    .gl
+                    .renderbuffer_storage(gl::RENDERBUFFER, format, width, height),
+            }
+            img.gl_depth_render_buffer = rb;
+            return Some(img);
+        }
+
+        // MSAA render targets render into a separate renderbuffer, which is
+        // resolved into the texture at the end of the pass.
+        if img.render_target && img.sample_count > 1 {
+            if let Some(storage) = self.gl_renderbuffer_storage_multisample {
+                let rb = self.gl.gen_renderbuffers(1)[0];
+                self.gl.bind_renderbuffer(gl::RENDERBUFFER, rb);
+                let samples = img.sample_count as GLsizei;
+                let format = img
+                    .pixel_format
+                    .gl_teximage_internal_format(self.is_gles2());
+                #[allow(unsafe_code)]
+                unsafe {
+                    storage(gl::RENDERBUFFER, samples, format, width, height)
+                };
+                img.gl_msaa_render_buffer = rb;
+            }
+        }
+
+        img.gl_sampler = self.cached_sampler(&img, desc.min_lod, desc.max_lod);
+
+        if img.ext_textures {
+            // Inject externally created GL textures.
+            for slot in 0..img.num_slots {
+                debug_assert!(desc.gl_textures[slot] != 0);
+                img.gl_tex[slot] = desc.gl_textures[slot];
+            }
+            return Some(img);
+        }
+
+        let is_compressed = img.pixel_format.is_compressed_pixel_format();
+        let internal_format = img
+            .pixel_format
+            .gl_teximage_internal_format(self.is_gles2());
+        let format = img.pixel_format.gl_teximage_format();
+        let ty = img.pixel_format.gl_teximage_type();
+        let num_faces = if img.image_type == ImageType::Cube {
+            ::CUBEFACE_NUM
+        } else {
+            1
+        };
+        // Immutable images get immutable storage for all mipmap levels,
+        // which is then filled with glTexSubImage*().
+        let has_tex_storage = if is_layered {
+            self.gl_tex_storage_3d.is_some()
+        } else {
+            self.gl_tex_storage_2d.is_some()
+        };
+        img.gl_immutable_storage =
+            img.usage == Usage::Immutable && !is_compressed && has_tex_storage;
+        for slot in 0..img.num_slots {
+            let tex = self.gl.gen_textures(1)[0];
+            img.gl_tex[slot] = tex;
+            self.bind_texture(0, img.gl_target, tex);
+            let min_filter = gl_min_filter(img.min_filter, img.num_mipmaps);
+            self.gl
+                .tex_parameter_i(img.gl_target, gl::TEXTURE_MIN_FILTER, min_filter as GLint);
+            self.gl.tex_parameter_i(
+                img.gl_target,
+                gl::TEXTURE_MAG_FILTER,
+                img.mag_filter.gl_filter() as GLint,
+            );
+            if self.ext_anisotropic && img.max_anisotropy > 1 {
+                let max_aniso = (img.max_anisotropy as GLint).min(self.max_anisotropy);
+                self.gl
+                    .tex_parameter_i(img.gl_target, GL_TEXTURE_MAX_ANISOTROPY_EXT, max_aniso);
+            }
+            if img.image_type == ImageType::Cube {
+                self.gl.tex_parameter_i(
+                    img.gl_target,
+                    gl::TEXTURE_WRAP_S,
+                    gl::CLAMP_TO_EDGE as GLint,
+                );
+                self.gl.tex_parameter_i(
+                    img.gl_target,
+                    gl::TEXTURE_WRAP_T,
+                    gl::CLAMP_TO_EDGE as GLint,
+                );
+            } else {
+                self.gl.tex_parameter_i(
+                    img.gl_target,
+                    gl::TEXTURE_WRAP_S,
+                    img.wrap_u.gl_wrap() as GLint,
+                );
+                self.gl.tex_parameter_i(
+                    img.gl_target,
+                    gl::TEXTURE_WRAP_T,
+                    img.wrap_v.gl_wrap() as GLint,
+                );
+                if img.image_type == ImageType::Texture3D {
+                    self.gl.tex_parameter_i(
+                        img.gl_target,
+                        gl::TEXTURE_WRAP_R,
+                        img.wrap_w.gl_wrap() as GLint,
+                    );
+                }
+            }
+            if !self.is_gles2() {
+                self.gl
+                    .tex_parameter_f(img.gl_target, gl::TEXTURE_MIN_LOD, desc.min_lod);
+                self.gl
+                    .tex_parameter_f(img.gl_target, gl::TEXTURE_MAX_LOD, desc.max_lod);
+                if desc.swizzle != ::TextureSwizzle::IDENTITY {
+                    for (&pname, swizzle) in GL_TEXTURE_SWIZZLE.iter().zip(&desc.swizzle) {
+                        self.gl.tex_parameter_i(
+                            img.gl_target,
+                            pname,
+                            swizzle.gl_swizzle() as GLint,
+                        );
+                    }
+                }
+            }
+
+            if img.gl_immutable_storage {
+                self.create_tex_storage(&img, internal_format, &desc.content);
+                continue;
+            }
+            for mip in 0..img.num_mipmaps {
+                let mip_width = (width >> mip).max(1);
+                let mip_height = (height >> mip).max(1);
+                if is_layered {
+                    let content = &desc.content.subimage[mip][0].content;
+                    let data = if content.is_empty() {
+                        None
+                    } else {
+                        Some(&content[..])
+                    };
+                    self.gl.tex_image_3d(
+                        img.gl_target,
+                        mip as GLint,
+                        internal_format as GLint,
+                        mip_width,
+                        mip_height,
+                        mip_depth(img.image_type, img.depth, mip) as GLsizei,
+                        0,
+                        format,
+                        ty,
+                        data,
+                    );
+                    continue;
+                }
+                for face in 0..num_faces {
+                    let target = if img.image_type == ImageType::Cube {
+                        gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as GLenum
+                    } else {
+                        img.gl_target
+                    };
+                    let content = &desc.content.subimage[mip][face].content;
+                    if is_compressed {
+                        self.gl.compressed_tex_image_2d(
+                            target,
+                            mip as GLint,
+                            internal_format,
+                            mip_width,
+                            mip_height,
+                            0,
+                            content,
+                        );
+                    } else {
+                        let data = if content.is_empty() {
+                            None
+                        } else {
+                            Some(&content[..])
+                        };
+                        self.gl.tex_image_2d(
+                            target,
+                            mip as GLint,
+                            internal_format as GLint,
+                            mip_width,
+                            mip_height,
+                            0,
+                            format,
+                            ty,
+                            data,
+                        );
+                    }
+                }
+            }
+        }
+        self.reset_texture_bindings();
+        Some(img)
+    }
+
+    /// Allocate immutable storage for all mipmap levels of the bound texture
+    /// of an image, and upload the content.
+    fn create_tex_storage(
+        &self,
+        img: &ImageResource,
+        internal_format: GLenum,
+        content: &ImageContent,
+    ) {
+        let levels = img.num_mipmaps as GLsizei;
+        let width = img.width as GLsizei;
+        let height = img.height as GLsizei;
+        match img.image_type {
+            ImageType::Texture2D | ImageType::Cube => {
+                let storage = self.gl_tex_storage_2d.unwrap();
+                #[allow(unsafe_code)]
+                unsafe {
+                    storage(img.gl_target, levels, internal_format, width, height)
+                };
+            }
+            ImageType::Texture3D | ImageType::Array => {
+                let storage = self.gl_tex_storage_3d.unwrap();
+                let depth = img.depth as GLsizei;
+                #[allow(unsafe_code)]
+                unsafe {
+                    storage(img.gl_target, levels, internal_format, width, height, depth)
+                };
+            }
+        }
+
+        let format = img.pixel_format.gl_teximage_format();
+        let ty = img.pixel_format.gl_teximage_type();
+        let num_faces = if img.image_type == ImageType::Cube {
+            ::CUBEFACE_NUM
+        } else {
+            1
+        };
+        for mip in 0..img.num_mipmaps {
+            let mip_width = (width >> mip).max(1);
+            let mip_height = (height >> mip).max(1);
+            for face in 0..num_faces {
+                let data = &content.subimage[mip][face].content;
+                if data.is_empty() {
+                    continue;
+                }
+                match img.image_type {
+                    ImageType::Texture2D | ImageType::Cube => {
+                        let target = if img.image_type == ImageType::Cube {
+                            gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as GLenum
+                        } else {
+                            img.gl_target
+                        };
+                        self.gl.tex_sub_image_2d(
+                            target,
+                            mip as GLint,
+                            0,
+                            0,
+                            mip_width,
+                            mip_height,
+                            format,
+                            ty,
+                            data,
+                        );
+                    }
+                    ImageType::Texture3D | ImageType::Array => {
+                        self.gl.tex_sub_image_3d(
+                            img.gl_target,
+                            mip as GLint,
+                            0,
+                            0,
+                            0,
+                            mip_width,
+                            mip_height,
+                            mip_depth(img.image_type, img.depth, mip) as GLint,
+                            format,
+                            ty,
+                            data,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn destroy_image(&mut self, img: ImageResource) {
+        // The GL objects of resources lost with their context are gone.
+        if img.slot.state != ResourceState::Valid {
+            return;
+        }
+        self.reset_texture_bindings();
+        if !img.ext_textures {
+            let textures: Vec<GLuint> = img
+                .gl_tex
+                .iter()
+                .take(img.num_slots)
+                .cloned()
+                .filter(|&tex| tex != 0)
+                .collect();
+            if !textures.is_empty() {
+                self.gl.delete_textures(&textures);
+            }
+        }
+        if img.gl_depth_render_buffer != 0 {
+            self.gl.delete_renderbuffers(&[img.gl_depth_render_buffer]);
+        }
+        if img.gl_msaa_render_buffer != 0 {
+            self.gl.delete_renderbuffers(&[img.gl_msaa_render_buffer]);
+        }
+    }
+
+    /// Create the framebuffer of a pass rendering into `color_images` and
+    /// the optional `ds_image`, which must match the attachments of `desc`.
+    ///
+    /// MSAA render targets render into their multisampled renderbuffer, and
+    /// only their attachments that are resolved get a resolve framebuffer,
+    /// which `end_pass()` blits into.
+    pub fn create_pass(
+        &mut self,
+        desc: &PassDesc,
+        color_images: Vec<ImageResource>,
+        ds_image: Option<ImageResource>,
+    ) -> Option<PassResource> {
+        let mut pass = PassResource::default();
+        for (att_desc, image) in desc.color_attachments.iter().zip(color_images) {
+            pass.color_atts.push(Attachment {
+                image,
+                image_id: att_desc.image,
+                mip_level: att_desc.mip_level,
+                slice: att_desc.index,
+                resolve: att_desc.resolve,
+                gl_msaa_resolve_buffer: 0,
+            });
+        }
+        if let (Some(att_desc), Some(image)) = (desc.depth_stencil_attachment.as_ref(), ds_image) {
+            pass.ds_att = Attachment {
+                image,
+                image_id: att_desc.image,
+                mip_level: att_desc.mip_level,
+                slice: att_desc.index,
+                resolve: false,
+                gl_msaa_resolve_buffer: 0,
+            };
+        }
+        let is_msaa = pass
+            .color_atts
+            .first()
+            .is_some_and(|att| att.image.gl_msaa_render_buffer != 0);
+
+        pass.gl_fb = self.gl.gen_framebuffers(1)[0];
+        self.gl.bind_framebuffer(gl::FRAMEBUFFER, pass.gl_fb);
+        for (i, att) in pass.color_atts.iter().enumerate() {
+            if is_msaa {
+                self.gl.framebuffer_renderbuffer(
+                    gl::FRAMEBUFFER,
+                    COLOR_ATTACHMENTS[i],
+                    gl::RENDERBUFFER,
+                    att.image.gl_msaa_render_buffer,
+                );
+            } else {
+                self.attach_texture(COLOR_ATTACHMENTS[i], att);
+            }
+        }
+        let ds_rb = pass.ds_att.image.gl_depth_render_buffer;
+        if ds_rb != 0 {
+            let has_stencil = pass.ds_att.image.pixel_format.is_depth_stencil_format();
+            let attachments: &[GLenum] = if !has_stencil {
+                &[gl::DEPTH_ATTACHMENT]
+            } else if self.is_gles2() {
+                &[gl::DEPTH_ATTACHMENT, gl::STENCIL_ATTACHMENT]
+            } else {
+                &[gl::DEPTH_STENCIL_ATTACHMENT]
+            };
+            for &attachment in attachments {
+                self.gl.framebuffer_renderbuffer(
+                    gl::FRAMEBUFFER,
+                    attachment,
+                    gl::RENDERBUFFER,
+                    ds_rb,
+                );
+            }
+        }
+        // A depth-only framebuffer is incomplete on desktop GL unless it
+        // neither draws to nor reads from color buffers.
+        if pass.color_atts.is_empty() && !self.is_gles2() {
+            self.gl.draw_buffers(&[gl::NONE]);
+            self.gl.read_buffer(gl::NONE);
+        }
+        let mut complete = self.framebuffer_complete("pass");
+
+        if complete && is_msaa {
+            for i in 0..pass.color_atts.len() {
+                if !pass.color_atts[i].resolve {
+                    continue;
+                }
+                let fb = self.gl.gen_framebuffers(1)[0];
+                pass.color_atts[i].gl_msaa_resolve_buffer = fb;
+                self.gl.bind_framebuffer(gl::FRAMEBUFFER, fb);
+                self.attach_texture(gl::COLOR_ATTACHMENT0, &pass.color_atts[i]);
+                if !self.framebuffer_complete("MSAA resolve") {
+                    complete = false;
+                    break;
+                }
+            }
+        }
+
+        self.gl
+            .bind_framebuffer(gl::FRAMEBUFFER, self.default_framebuffer);
+        if !complete {
+            self.delete_pass_framebuffers(&pass);
+            return None;
+        }
+        Some(pass)
+    }
+
+    pub fn destroy_pass(&mut self, pass: PassResource) {
+        // The GL objects of resources lost with their context are gone.
+        if pass.slot.state == ResourceState::Valid {
+            self.delete_pass_framebuffers(&pass);
+        }
+    }
+
+    fn delete_pass_framebuffers(&self, pass: &PassResource) {
+        let framebuffers: Vec<GLuint> = Some(pass.gl_fb)
+            .into_iter()
+            .chain(pass.color_atts.iter().map(|att| att.gl_msaa_resolve_buffer))
+            .filter(|&fb| fb != 0)
+            .collect();
+        if !framebuffers.is_empty() {
+            self.gl.delete_framebuffers(&framebuffers);
+        }
+    }
+
+    /// Attach the subimage of `att` to the bound framebuffer.
+    fn attach_texture(&self, attachment: GLenum, att: &Attachment) {
+        let tex = att.image.gl_tex[0];
+        let level = att.mip_level as GLint;
+        match att.image.image_type {
+            ImageType::Texture2D => self.gl.framebuffer_texture_2d(
+                gl::FRAMEBUFFER,
+                attachment,
+                gl::TEXTURE_2D,
+                tex,
+                level,
+            ),
+            ImageType::Cube => self.gl.framebuffer_texture_2d(
+                gl::FRAMEBUFFER,
+                attachment,
+                gl::TEXTURE_CUBE_MAP_POSITIVE_X + att.slice as GLenum,
+                tex,
+                level,
+            ),
+            ImageType::Texture3D | ImageType::Array => self.gl.framebuffer_texture_layer(
+                gl::FRAMEBUFFER,
+                attachment,
+                tex,
+                level,
+                att.slice as GLint,
+            ),
+        }
+    }
+
+    /// Check that the bound framebuffer is complete, warning if it isn't.
+    fn framebuffer_complete(&self, what: &str) -> bool {
+        let status = self.gl.check_frame_buffer_status(gl::FRAMEBUFFER);
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            warn!(
+                "create_pass: {} framebuffer incomplete (0x{:x})",
+                what, status
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Compile and link the shader program of `desc` from its GLSL sources,
+    /// and look up the locations of its uniforms and images.
+    pub fn create_shader(&mut self, desc: &ShaderDesc) -> Option<ShaderResource> {
+        let vs = self.compile_shader(gl::VERTEX_SHADER, &desc.vs)?;
+        let fs = match self.compile_shader(gl::FRAGMENT_SHADER, &desc.fs) {
+            Some(fs) => fs,
+            None => {
+                self.gl.delete_shader(vs);
+                return None;
+            }
+        };
+        let prog = self.gl.create_program();
+        self.gl.attach_shader(prog, vs);
+        self.gl.attach_shader(prog, fs);
+        self.gl.link_program(prog);
+        self.gl.delete_shader(vs);
+        self.gl.delete_shader(fs);
+        if self.gl.get_program_iv(prog, gl::LINK_STATUS) == 0 {
+            warn!(
+                "create_shader: failed to link program: {}",
+                self.gl.get_program_info_log(prog)
+            );
+            self.gl.delete_program(prog);
+            return None;
+        }
+
+        let mut shd = ShaderResource {
+            gl_prog: prog,
+            ..Default::default()
+        };
+        // The program must be bound to assign texture units to its samplers.
+        self.gl.use_program(prog);
+        let mut gl_tex_slot = 0;
+        for (stage, stage_desc) in shd.stage.iter_mut().zip(&[&desc.vs, &desc.fs]) {
+            for ub_desc in stage_desc
+                .uniform_blocks
+                .iter()
+                .take_while(|ub| ub.size > 0)
+            {
+                let mut ub = UniformBlock {
+                    size: ub_desc.size as usize,
+                    ..Default::default()
+                };
+                let mut offset = 0;
+                for u_desc in ub_desc.uniforms.iter().take_while(|u| !u.name.is_empty()) {
+                    let count = u_desc.array_count.max(1) as usize;
+                    ub.uniforms.push(Uniform {
+                        gl_loc: self.gl.get_uniform_location(prog, u_desc.name),
+                        uniform_type: u_desc.uniform_type,
+                        count: count as u8,
+                        offset: offset as u16,
+                    });
+                    offset += u_desc.uniform_type.bytesize(count);
+                }
+                stage.uniform_blocks.push(ub);
+            }
+            for img_desc in stage_desc
+                .images
+                .iter()
+                .take_while(|img| !img.name.is_empty())
+            {
+                let gl_loc = self.gl.get_uniform_location(prog, img_desc.name);
+                let mut img = ShaderImage {
+                    image_type: img_desc.image_type,
+                    gl_loc,
+                    gl_tex_slot: -1,
+                };
+                if gl_loc != -1 {
+                    self.gl.uniform_1i(gl_loc, gl_tex_slot);
+                    img.gl_tex_slot = gl_tex_slot;
+                    gl_tex_slot += 1;
+                }
+                stage.images.push(img);
+            }
+        }
+        // Force the next apply_draw_state() to bind its program again.
+        self.cache.cur_pipeline_id = ::Pipeline::default();
+        Some(shd)
+    }
+
+    pub fn destroy_shader(&mut self, shd: ShaderResource) {
+        // The GL objects of resources lost with their context are gone.
+        if shd.slot.state == ResourceState::Valid && shd.gl_prog != 0 {
+            self.gl.delete_program(shd.gl_prog);
+        }
+    }
+
+    fn compile_shader(&self, shader_type: GLenum, desc: &ShaderStageDesc) -> Option<GLuint> {
+        if desc.source.is_empty() {
+            warn!("create_shader: GL backend requires shader source code");
+            return None;
+        }
+        let shd = self.gl.create_shader(shader_type);
+        self.gl.shader_source(shd, &[desc.source.as_bytes()]);
+        self.gl.compile_shader(shd);
+        if self.gl.get_shader_iv(shd, gl::COMPILE_STATUS) == 0 {
+            warn!(
+                "create_shader: failed to compile shader: {}",
+                self.gl.get_shader_info_log(shd)
+            );
+            self.gl.delete_shader(shd);
+            return None;
+        }
+        Some(shd)
+    }
+
+    pub fn create_pipeline(
+        &mut self,
+        desc: PipelineDesc,
+        shader: ShaderResource,
+    ) -> Option<PipelineResource> {
+        if desc.primitive_restart && self.primitive_restart_cap().is_none() {
+            warn!("create_pipeline: primitive restart not supported by GL backend");
+            return None;
+        }
+        if desc.attachment_blends.is_some() && self.gl_indexed_blend_fns.is_none() {
+            warn!("create_pipeline: per-attachment blend state not supported by GL backend");
+            return None;
+        }
+        let instancing = self.query_feature(Feature::Instancing);
+        let mut pip = PipelineResource::default();
+        pip.gl_attrs
+            .resize(::MAX_VERTEX_ATTRIBUTES, GlAttr::default());
+        pip.vertex_layout_valid
+            .resize(::MAX_SHADERSTAGE_BUFFERS, false);
+        for (layout_index, layout) in desc.vertex_layouts.iter().enumerate() {
+            let (stride, divisor) = gl_stride_and_divisor(
+                layout.stride,
+                layout.step_func,
+                layout.step_rate,
+                instancing,
+            )?;
+            for attr in layout.attrs.iter().filter(|attr| !attr.name.is_empty()) {
+                let attr_loc = self.gl.get_attrib_location(shader.gl_prog, &attr.name);
+                if attr_loc < 0 || attr_loc as usize >= ::MAX_VERTEX_ATTRIBUTES {
+                    warn!(
+                        "create_pipeline: vertex attribute '{}' not found in shader",
+                        attr.name
+                    );
+                    pip.attrib_locations.push((attr.name.to_string(), -1));
+                    continue;
+                }
+                pip.attrib_locations.push((attr.name.to_string(), attr_loc));
+                let gl_attr = &mut pip.gl_attrs[attr_loc as usize];
+                gl_attr.vb_index = layout_index as i8;
+                gl_attr.divisor = divisor;
+                gl_attr.stride = stride;
+                gl_attr.offset = attr.offset;
+                gl_attr.size = attr.format.gl_vertex_format_size() as i8;
+                gl_attr.attr_type = attr.format.gl_vertex_format_type();
+                gl_attr.normalized = attr.format.gl_vertex_format_normalized() as i8;
+                pip.vertex_layout_valid[layout_index] = true;
+            }
+        }
+        pip.shader = shader;
+        pip.shader_id = desc.shader;
+        pip.primitive_type = desc.primitive_type;
+        pip.index_type = desc.index_type;
+        pip.primitive_restart = desc.primitive_restart;
+        pip.depth_stencil = desc.depth_stencil;
+        pip.blend = desc.blend;
+        pip.attachment_blends = desc.attachment_blends;
+        pip.rast = desc.rasterizer;
+        Some(pip)
+    }
+
+    pub fn update_buffer(&mut self, buf: &mut BufferResource, frame_index: u32, data: &[u8]) {
+        if buf.upd_frame_index == frame_index {
+            warn!("update_buffer: only one update per frame is allowed");
+            return;
+        }
+        if buf.append_frame_index == frame_index {
+            warn!("update_buffer: can't update a buffer that was appended to this frame");
+            return;
+        }
+        buf.upd_frame_index = frame_index;
+        buf.active_slot = (buf.active_slot + 1) % buf.gl_buf.len();
+
+        let target = buf.buffer_type.gl_buffer_target();
+        let gl_buf = buf.gl_buf[buf.active_slot];
+        if target == gl::ELEMENT_ARRAY_BUFFER {
+            self.cache.cur_gl_ib = gl_buf;
+        }
+        self.gl.bind_buffer(target, gl_buf);
+        if buf.usage == Usage::Stream {
+            // Orphan the previous storage, in case the GPU still reads from
+            // it despite the rotation over the inflight slots.
+            self.gl.buffer_data_untyped(
+                target,
+                buf.size as GLsizeiptr,
+                ptr::null(),
+                buf.usage.gl_usage(),
+            );
+        }
+        gl::buffer_sub_data(&*self.gl, target, 0, data);
+    }
+
+    pub fn append_buffer(
+        &mut self,
+        buf: &mut BufferResource,
+        frame_index: u32,
+        data: &[u8],
+    ) -> Option<usize> {
+        if buf.upd_frame_index == frame_index {
+            warn!("append_buffer: can't append to a buffer that was updated this frame");
+            return None;
+        }
+        // The first append of a frame starts over in the next slot.
+        if buf.append_frame_index != frame_index {
+            buf.append_frame_index = frame_index;
+            buf.append_pos = 0;
+            buf.active_slot = (buf.active_slot + 1) % buf.gl_buf.len();
+        }
+        let offset = buf.append_pos;
+        if offset + data.len() > buf.size {
+            warn!(
+                "append_buffer: {} bytes at offset {} overflow buffer of {} bytes",
+                data.len(),
+                offset,
+                buf.size
+            );
+            return None;
+        }
+
+        let target = buf.buffer_type.gl_buffer_target();
+        let gl_buf = buf.gl_buf[buf.active_slot];
+        if target == gl::ELEMENT_ARRAY_BUFFER {
+            self.cache.cur_gl_ib = gl_buf;
+        }
+        self.gl.bind_buffer(target, gl_buf);
+        gl::buffer_sub_data(&*self.gl, target, offset as isize, data);
+        // Keep appended vertex data 4-byte aligned.
+        buf.append_pos += (data.len() + 3) & !3;
+        Some(offset)
+    }
+
+    pub fn update_image(&mut self, img: &mut ImageResource, frame_index: u32, data: &ImageContent) {
+        if img.upd_frame_index == frame_index {
+            warn!("update_image: only one update per frame is allowed");
+            return;
+        }
+        img.upd_frame_index = frame_index;
+        img.active_slot = (img.active_slot + 1) % img.num_slots.max(1);
+        // Immutable images are never updated, so their storage can't be
+        // reallocated either.
+        debug_assert!(!img.gl_immutable_storage);
+
+        let gl_tex = img.gl_tex[img.active_slot];
+        self.bind_texture(0, img.gl_target, gl_tex);
+        let format = img.pixel_format.gl_teximage_format();
+        let ty = img.pixel_format.gl_teximage_type();
+        let num_faces = if img.image_type == ImageType::Cube {
+            ::CUBEFACE_NUM
+        } else {
+            1
+        };
+        for mip in 0..img.num_mipmaps {
+            let width = (img.width >> mip).max(1) as GLint;
+            let height = (img.height >> mip).max(1) as GLint;
+            for face in 0..num_faces {
+                let content = &data.subimage[mip][face].content;
+                match img.image_type {
+                    ImageType::Texture2D | ImageType::Cube => {
+                        let target = if img.image_type == ImageType::Cube {
+                            gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as GLenum
+                        } else {
+                            img.gl_target
+                        };
+                        self.gl.tex_sub_image_2d(
+                            target,
+                            mip as GLint,
+                            0,
+                            0,
+                            width,
+                            height,
+                            format,
+                            ty,
+                            content,
+                        );
+                    }
+                    ImageType::Texture3D | ImageType::Array => {
+                        let depth = mip_depth(img.image_type, img.depth, mip);
+                        self.gl.tex_sub_image_3d(
+                            img.gl_target,
+                            mip as GLint,
+                            0,
+                            0,
+                            0,
+                            width,
+                            height,
+                            depth as GLint,
+                            format,
+                            ty,
+                            content,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn generate_mipmaps(&mut self, img: &ImageResource) -> Result<(), ResourceError> {
+        // Go through the texture cache, so that the binding stays known
+        // to the next apply_draw_state().
+        self.bind_texture(0, img.gl_target, img.gl_tex[img.active_slot]);
+        self.gl.generate_mipmap(img.gl_target);
+        Ok(())
+    }
+
+    pub fn apply_draw_state(
+        &mut self,
+        pip: &PipelineResource,
+        vbs: &[&BufferResource],
+        ib: Option<&BufferResource>,
+        vs_imgs: &[&ImageResource],
+        fs_imgs: &[&ImageResource],
+    ) {
+        assert!(self.in_pass);
+        self.cache.cur_primitive_type = pip.primitive_type.gl_primitive_type();
+        self.cache.cur_index_type = pip.index_type;
+
+        // Update the render pipeline state, if it changed.
+        if self.cache.cur_pipeline_id.id != pip.slot.id {
+            self.cache.cur_pipeline_id = ::Pipeline { id: pip.slot.id };
+            self.apply_depth_stencil_state(&pip.depth_stencil);
+            self.apply_blend_state(&pip.blend);
+            if let Some(ref blends) = pip.attachment_blends {
+                let count = pip.blend.color_attachment_count as usize;
+                self.apply_attachment_blend_states(blends, count);
+            }
+            self.apply_rasterizer_state(&pip.rast);
+            self.apply_primitive_restart(pip.primitive_restart, pip.index_type);
+            match pip.primitive_type {
+                ::PrimitiveType::Lines | ::PrimitiveType::LineStrip => {
+                    self.apply_line_width(pip.rast.line_width);
+                }
+                _ => {}
+            }
+            self.gl.use_program(pip.shader.gl_prog);
         }
-    }
 
-    pub fn end_pass(&mut self) {
+        // Bind the images of both shader stages to their texture units.
+        for (stage, imgs) in pip.shader.stage.iter().zip([vs_imgs, fs_imgs].iter()) {
+            for (shd_img, img) in stage.images.iter().zip(imgs.iter()) {
+                if shd_img.gl_loc != -1 {
+                    let unit = shd_img.gl_tex_slot as usize;
+                    self.bind_texture(unit, img.gl_target, img.gl_tex[img.active_slot]);
+                    self.bind_sampler(unit, img.gl_sampler);
+                }
+            }
+        }
+
+        // The index buffer can be 0 for non-indexed rendering.
+        let gl_ib = ib.map_or(0, |ib| ib.gl_buf[ib.active_slot]);
+        if self.cache.cur_gl_ib != gl_ib {
+            self.cache.cur_gl_ib = gl_ib;
+            self.gl.bind_buffer(gl::ELEMENT_ARRAY_BUFFER, gl_ib);
+        }
+
+        self.apply_vertex_attributes(&pip.gl_attrs, vbs);
+    }
+
+    pub fn apply_blend_color(&mut self, color: [f32; 4]) {
+        if self.cache.blend.blend_color != color {
+            self.cache.blend.blend_color = color;
+            self.gl.blend_color(color[0], color[1], color[2], color[3]);
+        }
+        // Force the next draw state to apply its pipeline's blend color,
+        // even if the pipeline doesn't change.
+        self.cache.cur_pipeline_id = ::Pipeline::default();
+    }
+
+    pub fn apply_stencil_ref(&mut self, stencil_ref: u8) {
+        let cache_ds = &mut self.cache.ds;
+        if cache_ds.stencil_ref != stencil_ref {
+            cache_ds.stencil_ref = stencil_ref;
+            let faces = [
+                (gl::FRONT, cache_ds.stencil_front.compare_func),
+                (gl::BACK, cache_ds.stencil_back.compare_func),
+            ];
+            for &(gl_face, compare_func) in &faces {
+                self.gl.stencil_func_separate(
+                    gl_face,
+                    compare_func.gl_compare_func(),
+                    GLint::from(stencil_ref),
+                    GLuint::from(cache_ds.stencil_read_mask),
+                );
+            }
+        }
+        // Force the next draw state to apply its pipeline's stencil
+        // reference value, even if the pipeline doesn't change.
+        self.cache.cur_pipeline_id = ::Pipeline::default();
+    }
+
+    pub fn apply_uniform_block(
+        &mut self,
+        stage: ShaderStage,
+        ub_index: u32,
+        data: *const os::raw::c_void,
+        num_bytes: u32,
+    ) {
         unimplemented!();
     }
 
+    pub fn draw(&mut self, base_element: u32, num_elements: u32, num_instances: u32) {
+        let p_type: GLenum = self.cache.cur_primitive_type;
+        let call = draw_call(
+            self.cache.cur_index_type,
+            base_element,
+            num_elements,
+            num_instances,
+            self.query_feature(Feature::Instancing),
+        );
+        match call {
+            Some(DrawCall::Arrays { first, count }) => {
+                self.gl.draw_arrays(p_type, first, count);
+            }
+            Some(DrawCall::ArraysInstanced {
+                first,
+                count,
+                instances,
+            }) => {
+                self.gl
+                    .draw_arrays_instanced(p_type, first, count, instances);
+            }
+            Some(DrawCall::Elements {
+                index_type,
+                offset,
+                count,
+            }) => {
+                self.gl.draw_elements(p_type, count, index_type, offset);
+            }
+            Some(DrawCall::ElementsInstanced {
+                index_type,
+                offset,
+                count,
+                instances,
+            }) => {
+                self.gl
+                    .draw_elements_instanced(p_type, count, index_type, offset, instances);
+            }
+            None => {}
+        }
+    }
+
+    pub fn end_pass(&mut self, pass: Option<&PassResource>) {
+        assert!(self.in_pass);
+        if let Some(pass) = pass {
+            let needs_resolve = pass
+                .color_atts
+                .iter()
+                .any(|att| att.gl_msaa_resolve_buffer != 0);
+            if needs_resolve {
+                self.gl.bind_framebuffer(gl::READ_FRAMEBUFFER, pass.gl_fb);
+                let w = pass.color_atts[0].image.width as GLint;
+                let h = pass.color_atts[0].image.height as GLint;
+                for (i, att) in pass.color_atts.iter().enumerate() {
+                    // Attachments that aren't resolved keep their content
+                    // in the multisampled renderbuffer.
+                    if att.gl_msaa_resolve_buffer == 0 {
+                        continue;
+                    }
+                    self.gl
+                        .bind_framebuffer(gl::DRAW_FRAMEBUFFER, att.gl_msaa_resolve_buffer);
+                    self.gl.read_buffer(COLOR_ATTACHMENTS[i]);
+                    self.gl.draw_buffers(&[gl::COLOR_ATTACHMENT0]);
+                    self.gl.blit_framebuffer(
+                        0,
+                        0,
+                        w,
+                        h,
+                        0,
+                        0,
+                        w,
+                        h,
+                        gl::COLOR_BUFFER_BIT,
+                        gl::NEAREST,
+                    );
+                }
+            }
+        }
+        self.cur_pass_id = ::Pass::default();
+        self.cur_pass_width = 0;
+        self.cur_pass_height = 0;
+        self.gl
+            .bind_framebuffer(gl::FRAMEBUFFER, self.default_framebuffer);
+        self.in_pass = false;
+    }
+
     pub fn commit(&mut self) {
-        unimplemented!();
+        assert!(!self.in_pass);
+        self.gl.flush();
+        let submitted_frame = self.active_frame;
+        self.active_frame = (self.active_frame + 1) % ::NUM_INFLIGHT_FRAMES;
+
+        // Fence the frame that was just submitted, then wait until the GPU is
+        // done with the frame whose resource slots are about to be reused.
+        // Without fences, rely on the driver to synchronize.
+        if !self.is_gles2() {
+            let fence = self.gl.fence_sync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+            self.frame_fences[submitted_frame] = Some(fence);
+            if let Some(fence) = self.frame_fences[self.active_frame].take() {
+                self.gl
+                    .client_wait_sync(fence, gl::SYNC_FLUSH_COMMANDS_BIT, FRAME_FENCE_TIMEOUT);
+                self.gl.delete_sync(fence);
+            }
+        }
+    }
+}
+
+const COLOR_ATTACHMENTS: [GLenum; ::MAX_COLOR_ATTACHMENTS] = [
+    gl::COLOR_ATTACHMENT0,
+    gl::COLOR_ATTACHMENT1,
+    gl::COLOR_ATTACHMENT2,
+    gl::COLOR_ATTACHMENT3,
+];
+
+/// Clear the color attachment at `index` of the bound framebuffer to `val`.
+fn clear_color_attachment(clear_buffer_fv: ClearBufferfvFn, index: usize, val: &[f32; 4]) {
+    #[allow(unsafe_code)]
+    unsafe {
+        clear_buffer_fv(gl::COLOR, index as GLint, val.as_ptr())
+    };
+}
+
+/// The GL min filter for an image with `num_mipmaps` mipmaps.
+fn gl_min_filter(filter: ::Filter, num_mipmaps: usize) -> GLenum {
+    if num_mipmaps > 1 {
+        filter.gl_filter()
+    } else {
+        // Without mipmaps, a mipmapped min filter leaves the texture
+        // incomplete.
+        match filter {
+            ::Filter::Nearest | ::Filter::NearestMipmapNearest | ::Filter::NearestMipmapLinear => {
+                gl::NEAREST
+            }
+            _ => gl::LINEAR,
+        }
+    }
+}
+
+/// The key of the cached sampler object for the sampler state of an image.
+///
+/// Cube maps are always clamped to their edges, like their texture
+/// parameters, because a bound sampler object overrides those.
+fn sampler_key(img: &ImageResource, min_lod: f32, max_lod: f32) -> SamplerKey {
+    let (wrap_u, wrap_v, wrap_w) = if img.image_type == ImageType::Cube {
+        (
+            ::Wrap::ClampToEdge,
+            ::Wrap::ClampToEdge,
+            ::Wrap::ClampToEdge,
+        )
+    } else {
+        (img.wrap_u, img.wrap_v, img.wrap_w)
+    };
+    SamplerKey {
+        min_filter: gl_min_filter(img.min_filter, img.num_mipmaps),
+        mag_filter: img.mag_filter,
+        wrap_u,
+        wrap_v,
+        wrap_w,
+        max_anisotropy: img.max_anisotropy,
+        min_lod_bits: min_lod.to_bits(),
+        max_lod_bits: max_lod.to_bits(),
+    }
+}
+
+/// A GL draw call, as chosen by `draw_call()`.
+#[derive(Debug, PartialEq)]
+enum DrawCall {
+    Arrays {
+        first: GLint,
+        count: GLint,
+    },
+    ArraysInstanced {
+        first: GLint,
+        count: GLint,
+        instances: GLint,
+    },
+    Elements {
+        index_type: GLenum,
+        offset: u32,
+        count: GLint,
+    },
+    ElementsInstanced {
+        index_type: GLenum,
+        offset: u32,
+        count: GLint,
+        instances: GLint,
+    },
+}
+
+/// Choose the GL draw call for a draw with the given index type, or `None`
+/// if nothing is drawn.
+///
+/// Without instancing support, the instanced entry points aren't available,
+/// so instanced draws are silently dropped.
+fn draw_call(
+    index_type: Option<::IndexType>,
+    base_element: u32,
+    num_elements: u32,
+    num_instances: u32,
+    instancing: bool,
+) -> Option<DrawCall> {
+    if num_instances == 0 {
+        return None;
+    }
+    let instanced = num_instances > 1;
+    if instanced && !instancing {
+        return None;
     }
+
+    // OpenGL expects these to be signed integers.
+    let count = num_elements as GLint;
+    let instances = num_instances as GLint;
+
+    Some(match index_type {
+        Some(index_type) => {
+            let offset = base_element * index_type.bytesize() as u32;
+            let index_type = index_type.gl_index_type();
+            if instanced {
+                DrawCall::ElementsInstanced {
+                    index_type,
+                    offset,
+                    count,
+                    instances,
+                }
+            } else {
+                DrawCall::Elements {
+                    index_type,
+                    offset,
+                    count,
+                }
+            }
+        }
+        None => {
+            let first = base_element as GLint;
+            if instanced {
+                DrawCall::ArraysInstanced {
+                    first,
+                    count,
+                    instances,
+                }
+            } else {
+                DrawCall::Arrays { first, count }
+            }
+        }
+    })
 }
 
+/// The GL stride and divisor of the attributes of a vertex layout, or
+/// `None` if the layout can't be expressed in GL.
+fn gl_stride_and_divisor(
+    stride: u32,
+    step_func: VertexStep,
+    step_rate: u32,
+    instancing: bool,
+) -> Option<(GLint, GLint)> {
+    let divisor = match step_func {
+        VertexStep::PerVertex => 0,
+        VertexStep::PerInstance => {
+            if !instancing {
+                warn!("create_pipeline: per-instance vertex data requires instancing");
+                return None;
+            }
+            step_rate
+        }
+    };
+    if stride > GLint::MAX as u32 || divisor > GLint::MAX as u32 {
+        warn!(
+            "create_pipeline: vertex stride {} or step rate {} out of range",
+            stride, divisor
+        );
+        return None;
+    }
+    Some((stride as GLint, divisor as GLint))
+}
+
+/// The `glClear()` mask for the attachments that a pass action clears.
+/// Only the first color attachment is taken into account, and attachments
+/// the pass doesn't have are left out.
+fn clear_mask(
+    action: &PassAction,
+    has_color: bool,
+    has_depth: bool,
+    has_stencil: bool,
+) -> GLbitfield {
+    let mut mask = 0;
+    if has_color && action.colors[0].action == Action::Clear {
+        mask |= gl::COLOR_BUFFER_BIT;
+    }
+    if has_depth && action.depth.action == Action::Clear {
+        mask |= gl::DEPTH_BUFFER_BIT;
+    }
+    if has_stencil && action.stencil.action == Action::Clear {
+        mask |= gl::STENCIL_BUFFER_BIT;
+    }
+    mask
+}
+
+/// The size of a texture dimension at mipmap level `mip`.
+fn mip_size(size: usize, mip: usize) -> usize {
+    (size >> mip).max(1)
+}
+
+/// The depth of a 3D texture, or the layer count of an array texture, at
+/// mipmap level `mip`. Array textures keep all layers in every mipmap.
+fn mip_depth(image_type: ImageType, depth_or_layers: usize, mip: usize) -> usize {
+    if image_type == ImageType::Array {
+        depth_or_layers
+    } else {
+        mip_size(depth_or_layers, mip)
+    }
+}
+
+/// The byte size of one 2D slice of a texture at mipmap level `mip`.
+fn mip_slice_size(fmt: PixelFormat, width: usize, height: usize, mip: usize) -> usize {
+    fmt.surface_pitch(mip_size(width, mip), mip_size(height, mip))
+}
+
+/// Clamp a line width to the `(min, max)` range supported by the driver.
+fn clamp_line_width(line_width: f32, range: (f32, f32)) -> f32 {
+    line_width.max(range.0).min(range.1)
+}
+
+#[derive(Default)]
 struct CacheAttribute {
     gl_attr: GlAttr,
     gl_vbuf: GLuint,
@@ -347,12 +2755,17 @@ struct CacheAttribute {
 struct ContextCache {
     ds: ::DepthStencilState,
     blend: ::BlendState,
+    blend_indexed: bool,
     rast: ::RasterizerState,
     polygon_offset_enabled: bool,
+    primitive_restart: bool,
+    primitive_restart_index: GLuint,
     attrs: Vec<CacheAttribute>,
+    textures: Vec<(GLenum, GLuint)>,
+    samplers: Vec<GLuint>,
     cur_gl_ib: GLuint,
     cur_primitive_type: GLenum,
-    cur_index_type: GLenum,
+    cur_index_type: Option<::IndexType>,
     cur_pipeline: PipelineResource, // TODO why was this a pointer?
     cur_pipeline_id: ::Pipeline,
 }
@@ -362,14 +2775,175 @@ impl Default for ContextCache {
         ContextCache {
             ds: ::DepthStencilState::default(),
             blend: ::BlendState::default(),
+            blend_indexed: false,
             rast: ::RasterizerState::default(),
             polygon_offset_enabled: false,
-            attrs: Vec::with_capacity(::MAX_VERTEX_ATTRIBUTES),
+            primitive_restart: false,
+            primitive_restart_index: 0,
+            attrs: (0..::MAX_VERTEX_ATTRIBUTES)
+                .map(|_| CacheAttribute::default())
+                .collect(),
+            textures: vec![(0, 0); ::NUM_SHADER_STAGES * ::MAX_SHADERSTAGE_IMAGES],
+            samplers: vec![0; ::NUM_SHADER_STAGES * ::MAX_SHADERSTAGE_IMAGES],
             cur_gl_ib: 0,
             cur_primitive_type: gl::TRIANGLES,
-            cur_index_type: 0,
+            cur_index_type: None,
             cur_pipeline: PipelineResource::default(),
             cur_pipeline_id: ::Pipeline::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_BITS: GLbitfield =
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT;
+
+    #[test]
+    fn clear_mask_follows_the_pass_action() {
+        let action = PassAction::clear([0.0; 4]);
+        assert_eq!(clear_mask(&action, true, true, true), ALL_BITS);
+
+        let mut action = PassAction::clear([0.0; 4]);
+        action.colors[0].action = Action::Load;
+        action.stencil.action = Action::DontCare;
+        assert_eq!(clear_mask(&action, true, true, true), gl::DEPTH_BUFFER_BIT);
+    }
+
+    #[test]
+    fn clear_mask_skips_missing_attachments() {
+        let action = PassAction::clear([0.0; 4]);
+        assert_eq!(
+            clear_mask(&action, false, true, true),
+            gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT
+        );
+        assert_eq!(
+            clear_mask(&action, true, true, false),
+            gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT
+        );
+        assert_eq!(
+            clear_mask(&action, true, false, false),
+            gl::COLOR_BUFFER_BIT
+        );
+        assert_eq!(clear_mask(&action, false, false, false), 0);
+    }
+
+    #[test]
+    fn draw_call_dispatches_on_index_type_and_instances() {
+        assert_eq!(
+            draw_call(None, 3, 6, 1, true),
+            Some(DrawCall::Arrays { first: 3, count: 6 })
+        );
+        assert_eq!(
+            draw_call(None, 3, 6, 4, true),
+            Some(DrawCall::ArraysInstanced {
+                first: 3,
+                count: 6,
+                instances: 4,
+            })
+        );
+        assert_eq!(
+            draw_call(Some(::IndexType::UInt16), 3, 6, 1, true),
+            Some(DrawCall::Elements {
+                index_type: gl::UNSIGNED_SHORT,
+                offset: 6,
+                count: 6,
+            })
+        );
+        assert_eq!(
+            draw_call(Some(::IndexType::UInt32), 3, 6, 4, true),
+            Some(DrawCall::ElementsInstanced {
+                index_type: gl::UNSIGNED_INT,
+                offset: 12,
+                count: 6,
+                instances: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn draw_call_skips_empty_and_unsupported_instanced_draws() {
+        assert_eq!(draw_call(None, 0, 6, 0, true), None);
+        assert_eq!(draw_call(Some(::IndexType::UInt16), 0, 6, 0, true), None);
+        assert_eq!(draw_call(None, 0, 6, 2, false), None);
+        assert_eq!(
+            draw_call(None, 0, 6, 1, false),
+            Some(DrawCall::Arrays { first: 0, count: 6 })
+        );
+    }
+
+    fn sampled_image(image_type: ImageType, wrap: ::Wrap) -> ImageResource {
+        ImageResource {
+            image_type,
+            num_mipmaps: 1,
+            min_filter: ::Filter::Linear,
+            mag_filter: ::Filter::Linear,
+            wrap_u: wrap,
+            wrap_v: wrap,
+            wrap_w: wrap,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn identical_sampler_states_share_a_cache_entry() {
+        let a = sampled_image(ImageType::Texture2D, ::Wrap::Repeat);
+        let b = sampled_image(ImageType::Texture2D, ::Wrap::Repeat);
+        let c = sampled_image(ImageType::Texture2D, ::Wrap::MirroredRepeat);
+        let mut cache = HashMap::new();
+        for img in &[&a, &b, &c] {
+            let len = cache.len();
+            cache
+                .entry(sampler_key(img, 0.0, 1000.0))
+                .or_insert(len as GLuint + 1);
+        }
+        assert_eq!(cache.len(), 2);
+        assert_eq!(sampler_key(&a, 0.0, 1000.0), sampler_key(&b, 0.0, 1000.0));
+        assert_ne!(sampler_key(&a, 0.0, 1000.0), sampler_key(&a, 0.0, 0.0));
+    }
+
+    #[test]
+    fn cube_map_samplers_clamp_to_edge() {
+        let cube = sampled_image(ImageType::Cube, ::Wrap::Repeat);
+        let key = sampler_key(&cube, 0.0, 1000.0);
+        assert_eq!(key.wrap_u, ::Wrap::ClampToEdge);
+        assert_eq!(key.wrap_v, ::Wrap::ClampToEdge);
+        assert_eq!(key.wrap_w, ::Wrap::ClampToEdge);
+        let clamped = sampled_image(ImageType::Cube, ::Wrap::ClampToEdge);
+        assert_eq!(sampler_key(&clamped, 0.0, 1000.0), key);
+    }
+
+    #[test]
+    fn per_instance_layouts_set_the_divisor() {
+        assert_eq!(
+            gl_stride_and_divisor(32, VertexStep::PerInstance, 1, true),
+            Some((32, 1))
+        );
+        assert_eq!(
+            gl_stride_and_divisor(32, VertexStep::PerVertex, 1, true),
+            Some((32, 0))
+        );
+        assert_eq!(
+            gl_stride_and_divisor(32, VertexStep::PerInstance, 1, false),
+            None
+        );
+    }
+
+    #[test]
+    fn large_strides_and_step_rates_are_kept_or_rejected() {
+        assert_eq!(
+            gl_stride_and_divisor(128, VertexStep::PerInstance, 300, true),
+            Some((128, 300))
+        );
+        assert_eq!(
+            gl_stride_and_divisor(u32::MAX, VertexStep::PerVertex, 0, true),
+            None
+        );
+        assert_eq!(
+            gl_stride_and_divisor(16, VertexStep::PerInstance, u32::MAX, true),
+            None
+        );
+    }
+}
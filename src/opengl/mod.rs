@@ -17,11 +17,13 @@ use opengl::gleam::gl::types::{GLenum, GLint, GLuint};
 
 /// GL backend buffer resource.
 pub struct BufferResource {
-    slot: ::pool::Slot,
-    size: usize,
-    buffer_type: ::BufferType, // Renamed from sokol field 'type' because type is a keyword.
-    usage: ::Usage,
+    pub slot: ::pool::Slot,
+    pub size: usize,
+    pub buffer_type: ::BufferType, // Renamed from sokol field 'type' because type is a keyword.
+    pub usage: ::Usage,
     upd_frame_index: u32,
+    append_frame_index: u32,
+    append_pos: usize,
     // num_slots: usize,
     active_slot: usize,
     gl_buf: Vec<GLuint>,
@@ -36,6 +38,8 @@ impl Default for BufferResource {
             buffer_type: ::BufferType::default(), // Renamed from sokol field 'type' because type is a keyword.
             usage: ::Usage::default(),
             upd_frame_index: 0,
+            append_frame_index: 0,
+            append_pos: 0,
             active_slot: 0,
             gl_buf: Vec::<GLuint>::with_capacity(::NUM_INFLIGHT_FRAMES),
             ext_buffers: false,
@@ -43,19 +47,38 @@ impl Default for BufferResource {
     }
 }
 
+impl BufferResource {
+    /// The frame index of the last update of the buffer, or 0 if it was
+    /// never updated.
+    pub fn upd_frame_index(&self) -> u32 {
+        self.upd_frame_index
+    }
+
+    /// The byte offset at which the next append during frame `frame_index`
+    /// writes its data.
+    pub fn append_pos(&self, frame_index: u32) -> usize {
+        if self.append_frame_index == frame_index {
+            self.append_pos
+        } else {
+            0
+        }
+    }
+}
+
 /// GL backend image resource
+#[derive(Clone)]
 pub struct ImageResource {
-    slot: ::pool::Slot,
-    image_type: ::ImageType,
-    render_target: bool,
-    width: usize,
-    height: usize,
-    depth: usize,
-    num_mipmaps: usize,
-    usage: ::Usage,
-    pixel_format: ::PixelFormat,
-    sample_count: usize,
-    min_filter: ::Filter,
+    pub slot: ::pool::Slot,
+    pub image_type: ::ImageType,
+    pub render_target: bool,
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub num_mipmaps: usize,
+    pub usage: ::Usage,
+    pub pixel_format: ::PixelFormat,
+    pub sample_count: usize,
+    pub min_filter: ::Filter,
     mag_filter: ::Filter,
     wrap_u: ::Wrap,
     wrap_v: ::Wrap,
@@ -64,6 +87,8 @@ pub struct ImageResource {
     gl_target: GLenum,
     gl_depth_render_buffer: GLuint,
     gl_msaa_render_buffer: GLuint,
+    gl_sampler: GLuint, // 0 if sampler objects aren't used
+    gl_immutable_storage: bool,
     upd_frame_index: u32,
     num_slots: usize,
     active_slot: usize,
@@ -95,6 +120,8 @@ impl Default for ImageResource {
             gl_target: 0,
             gl_depth_render_buffer: 0,
             gl_msaa_render_buffer: 0,
+            gl_sampler: 0,
+            gl_immutable_storage: false,
             upd_frame_index: 0,
             num_slots: 0,
             active_slot: 0,
@@ -104,7 +131,7 @@ impl Default for ImageResource {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct Uniform {
     gl_loc: GLint,
     uniform_type: ::UniformType,
@@ -112,34 +139,56 @@ struct Uniform {
     offset: u16,
 }
 
+#[derive(Clone)]
 struct UniformBlock {
+    size: usize,
     uniforms: Vec<Uniform>,
 }
 
 impl Default for UniformBlock {
     fn default() -> Self {
         UniformBlock {
+            size: 0,
             uniforms: Vec::<Uniform>::with_capacity(::MAX_UB_MEMBERS),
         }
     }
 }
 
+#[derive(Clone)]
+struct ShaderImage {
+    image_type: ::ImageType,
+    gl_loc: GLint, // -1 if the sampler isn't used by the shader
+    gl_tex_slot: GLint,
+}
+
+impl Default for ShaderImage {
+    fn default() -> Self {
+        ShaderImage {
+            image_type: ::ImageType::default(),
+            gl_loc: -1,
+            gl_tex_slot: -1,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct ShaderStage {
     uniform_blocks: Vec<UniformBlock>,
-    images: Vec<ImageResource>,
+    images: Vec<ShaderImage>,
 }
 
 impl Default for ShaderStage {
     fn default() -> Self {
         ShaderStage {
             uniform_blocks: Vec::<UniformBlock>::with_capacity(::MAX_SHADERSTAGE_UBS),
-            images: Vec::<ImageResource>::with_capacity(::MAX_SHADERSTAGE_IMAGES),
+            images: Vec::<ShaderImage>::with_capacity(::MAX_SHADERSTAGE_IMAGES),
         }
     }
 }
 
+#[derive(Clone)]
 pub struct ShaderResource {
-    slot: ::pool::Slot,
+    pub slot: ::pool::Slot,
     gl_prog: GLuint,
     stage: Vec<ShaderStage>,
 }
@@ -158,13 +207,14 @@ impl Default for ShaderResource {
     }
 }
 
+#[derive(Copy, Clone, PartialEq)]
 struct GlAttr {
-    vb_index: i8, // -1 if attr is not enabled
-    divisor: i8,  // -1 if not initialized
-    stride: i8,
+    vb_index: i8,   // -1 if attr is not enabled
+    divisor: GLint, // -1 if not initialized
+    stride: GLint,
     size: i8,
     normalized: i8,
-    offset: u8,
+    offset: GLuint,
     attr_type: GLenum,
 }
 
@@ -183,19 +233,22 @@ impl Default for GlAttr {
 }
 
 pub struct PipelineResource {
-    slot: ::pool::Slot,
+    pub slot: ::pool::Slot,
     shader: ShaderResource, // TODO why was this a pointer?
     shader_id: ::Shader,
     primitive_type: ::PrimitiveType,
-    index_type: ::IndexType,
+    index_type: Option<::IndexType>,
+    primitive_restart: bool,
     vertex_layout_valid: Vec<bool>,
     color_attachment_count: usize,
     color_format: ::PixelFormat,
     depth_format: ::PixelFormat,
     sample_count: usize,
     gl_attrs: Vec<GlAttr>,
+    attrib_locations: Vec<(String, GLint)>,
     depth_stencil: ::DepthStencilState,
     blend: ::BlendState,
+    attachment_blends: Option<[::BlendState; ::MAX_COLOR_ATTACHMENTS]>,
     rast: ::RasterizerState,
 }
 
@@ -208,33 +261,86 @@ impl Default for PipelineResource {
             shader: ShaderResource::default(), // TODO why was this a pointer?
             shader_id: ::Shader::default(),
             primitive_type: ::PrimitiveType::default(),
-            index_type: ::IndexType::UInt16,
+            index_type: None,
+            primitive_restart: false,
             vertex_layout_valid: Vec::with_capacity(::MAX_SHADERSTAGE_BUFFERS),
             color_attachment_count: 0,
             color_format: ::PixelFormat::default(),
             depth_format: ::PixelFormat::default(),
             sample_count: 0,
             gl_attrs: Vec::with_capacity(::MAX_VERTEX_ATTRIBUTES),
+            attrib_locations: Vec::new(),
             depth_stencil: ::DepthStencilState::default(),
             blend: ::BlendState::default(),
+            attachment_blends: None,
             rast: ::RasterizerState::default(),
         }
     }
 }
 
+impl ShaderResource {
+    /// The size in bytes of uniform block `index` of `stage`, or `None` if
+    /// the shader has no such uniform block.
+    pub fn uniform_block_size(&self, stage: ::ShaderStage, index: usize) -> Option<usize> {
+        let stage = match stage {
+            ::ShaderStage::VS => 0,
+            ::ShaderStage::FS => 1,
+        };
+        self.stage[stage]
+            .uniform_blocks
+            .get(index)
+            .map(|ub| ub.size)
+    }
+}
+
+impl PipelineResource {
+    /// Whether the pipeline reads vertex data from vertex buffer slot `index`.
+    pub fn uses_vertex_buffer(&self, index: usize) -> bool {
+        self.vertex_layout_valid
+            .get(index)
+            .cloned()
+            .unwrap_or(false)
+    }
+
+    /// The index type of the pipeline, or `None` for non-indexed rendering.
+    pub fn index_type(&self) -> Option<::IndexType> {
+        self.index_type
+    }
+
+    /// The named vertex attributes of the pipeline and the GL attribute
+    /// locations they resolved to, -1 if the shader doesn't use them.
+    pub fn attrib_locations(&self) -> &[(String, GLint)] {
+        &self.attrib_locations
+    }
+
+    /// The image type the pipeline's shader expects in image slot `index` of
+    /// `stage`, or `None` if the slot is unused.
+    pub fn shader_image_type(&self, stage: ::ShaderStage, index: usize) -> Option<::ImageType> {
+        let stage = match stage {
+            ::ShaderStage::VS => 0,
+            ::ShaderStage::FS => 1,
+        };
+        self.shader.stage[stage]
+            .images
+            .get(index)
+            .map(|img| img.image_type)
+    }
+}
+
 #[derive(Default)]
 pub struct Attachment {
-    image: ImageResource, // TODO why was this a pointer
+    pub image: ImageResource, // TODO why was this a pointer
     image_id: ::Image,
     mip_level: usize, // TODO was an int, does this need to be signed?
     slice: usize,     // TODO was an int, does this need to be signed?
-    gl_msaa_resolve_buffer: GLuint,
+    resolve: bool,
+    gl_msaa_resolve_buffer: GLuint, // 0 if the attachment isn't resolved
 }
 
 pub struct PassResource {
-    slot: ::pool::Slot,
+    pub slot: ::pool::Slot,
     gl_fb: GLuint,
-    color_atts: Vec<Attachment>,
+    pub color_atts: Vec<Attachment>,
     ds_att: Attachment,
 }
 
@@ -249,6 +355,34 @@ impl Default for PassResource {
     }
 }
 
+impl PassResource {
+    /// The width and height of the images the pass renders into.
+    pub fn size(&self) -> (usize, usize) {
+        let image = self
+            .color_atts
+            .first()
+            .map_or(&self.ds_att.image, |att| &att.image);
+        (image.width, image.height)
+    }
+
+    /// The images the pass renders into.
+    pub fn attachments(&self) -> ::PassAttachments {
+        let image = |att: &Attachment| ::AttachmentImage {
+            image: att.image_id,
+            mip_level: att.mip_level,
+            index: att.slice,
+        };
+        ::PassAttachments {
+            color_attachments: self.color_atts.iter().map(&image).collect(),
+            depth_stencil_attachment: if self.ds_att.image_id.is_valid() {
+                Some(image(&self.ds_att))
+            } else {
+                None
+            },
+        }
+    }
+}
+
 pub struct GlFunctionLookup {
     lookup_fn: fn(&str) -> *const os::raw::c_void,
 }
@@ -7,6 +7,56 @@
 use super::gleam::gl;
 use super::super::*;
 
+const GL_BGRA: gl::GLenum = 0x80E1;
+const GL_COMPRESSED_RGBA_S3TC_DXT1_EXT: gl::GLenum = 0x83F1;
+const GL_COMPRESSED_RGBA_S3TC_DXT3_EXT: gl::GLenum = 0x83F2;
+const GL_COMPRESSED_RGBA_S3TC_DXT5_EXT: gl::GLenum = 0x83F3;
+const GL_COMPRESSED_RGB_PVRTC_2BPPV1_IMG: gl::GLenum = 0x8C01;
+const GL_COMPRESSED_RGB_PVRTC_4BPPV1_IMG: gl::GLenum = 0x8C00;
+const GL_COMPRESSED_RGBA_PVRTC_2BPPV1_IMG: gl::GLenum = 0x8C03;
+const GL_COMPRESSED_RGBA_PVRTC_4BPPV1_IMG: gl::GLenum = 0x8C02;
+const GL_COMPRESSED_RGB8_ETC2: gl::GLenum = 0x9274;
+const GL_COMPRESSED_SRGB8_ETC2: gl::GLenum = 0x9275;
+
+impl BlendFactor {
+    /// Convert this blend factor to the OpenGL equivalent.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_blend_factor(self) -> gl::GLenum {
+        match self {
+            BlendFactor::Zero => gl::ZERO,
+            BlendFactor::One => gl::ONE,
+            BlendFactor::SrcColor => gl::SRC_COLOR,
+            BlendFactor::OneMinusSrcColor => gl::ONE_MINUS_SRC_COLOR,
+            BlendFactor::SrcAlpha => gl::SRC_ALPHA,
+            BlendFactor::OneMinusSrcAlpha => gl::ONE_MINUS_SRC_ALPHA,
+            BlendFactor::DstColor => gl::DST_COLOR,
+            BlendFactor::OneMinusDstColor => gl::ONE_MINUS_DST_COLOR,
+            BlendFactor::DstAlpha => gl::DST_ALPHA,
+            BlendFactor::OneMinusDstAlpha => gl::ONE_MINUS_DST_ALPHA,
+            BlendFactor::SrcAlphaSaturated => gl::SRC_ALPHA_SATURATE,
+            BlendFactor::BlendColor => gl::CONSTANT_COLOR,
+            BlendFactor::OneMinusBlendColor => gl::ONE_MINUS_CONSTANT_COLOR,
+            BlendFactor::BlendAlpha => gl::CONSTANT_ALPHA,
+            BlendFactor::OneMinusBlendAlpha => gl::ONE_MINUS_CONSTANT_ALPHA,
+        }
+    }
+}
+
+impl BlendOp {
+    /// Convert this blend operation to the OpenGL equivalent blend
+    /// equation.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_blend_op(self) -> gl::GLenum {
+        match self {
+            BlendOp::Add => gl::FUNC_ADD,
+            BlendOp::Subtract => gl::FUNC_SUBTRACT,
+            BlendOp::ReverseSubtract => gl::FUNC_REVERSE_SUBTRACT,
+        }
+    }
+}
+
 impl BufferType {
     /// Convert this buffer type to the OpenGL equivalent.
     ///
@@ -19,6 +69,80 @@ impl BufferType {
     }
 }
 
+impl CompareFunc {
+    /// Convert this comparison function to the OpenGL equivalent.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_compare_func(self) -> gl::GLenum {
+        match self {
+            CompareFunc::Never => gl::NEVER,
+            CompareFunc::Less => gl::LESS,
+            CompareFunc::Equal => gl::EQUAL,
+            CompareFunc::LessEqual => gl::LEQUAL,
+            CompareFunc::Greater => gl::GREATER,
+            CompareFunc::NotEqual => gl::NOTEQUAL,
+            CompareFunc::GreaterEqual => gl::GEQUAL,
+            CompareFunc::Always => gl::ALWAYS,
+        }
+    }
+}
+
+impl CullMode {
+    /// Convert this cull mode to the OpenGL equivalent face for `glCullFace`.
+    ///
+    /// `CullMode::None` has no equivalent, culling must be disabled instead.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_cull_face(self) -> gl::GLenum {
+        match self {
+            CullMode::None => 0,
+            CullMode::Front => gl::FRONT,
+            CullMode::Back => gl::BACK,
+        }
+    }
+}
+
+impl FaceWinding {
+    /// Convert this face winding to the OpenGL equivalent.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_front_face(self) -> gl::GLenum {
+        match self {
+            FaceWinding::CW => gl::CW,
+            FaceWinding::CCW => gl::CCW,
+        }
+    }
+}
+
+impl PolygonMode {
+    /// Convert this polygon mode to the OpenGL equivalent.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_polygon_mode(self) -> gl::GLenum {
+        match self {
+            PolygonMode::Fill => gl::FILL,
+            PolygonMode::Line => gl::LINE,
+            PolygonMode::Point => gl::POINT,
+        }
+    }
+}
+
+impl Filter {
+    /// Convert this filter mode to the OpenGL equivalent.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_filter(self) -> gl::GLenum {
+        match self {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Linear => gl::LINEAR,
+            Filter::NearestMipmapNearest => gl::NEAREST_MIPMAP_NEAREST,
+            Filter::NearestMipmapLinear => gl::NEAREST_MIPMAP_LINEAR,
+            Filter::LinearMipmapNearest => gl::LINEAR_MIPMAP_NEAREST,
+            Filter::LinearMipmapLinear => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+}
+
 impl ImageType {
     /// Convert this image type to the OpenGL equivalent.
     ///
@@ -37,6 +161,153 @@ impl ImageType {
     }
 }
 
+impl IndexType {
+    /// Convert this index type to the OpenGL equivalent.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_index_type(self) -> gl::GLenum {
+        match self {
+            IndexType::UInt16 => gl::UNSIGNED_SHORT,
+            IndexType::UInt32 => gl::UNSIGNED_INT,
+        }
+    }
+}
+
+impl PixelFormat {
+    /// Convert this pixel format to the OpenGL equivalent pixel data
+    /// format, as used by `glTexImage2D` and `glReadPixels`.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_teximage_format(self) -> gl::GLenum {
+        match self {
+            PixelFormat::None => 0,
+            PixelFormat::RGBA8
+            | PixelFormat::R5G5B5A1
+            | PixelFormat::RGBA4
+            | PixelFormat::RGBA32F
+            | PixelFormat::RGBA16F
+            | PixelFormat::R10G10B10A2 => gl::RGBA,
+            PixelFormat::BGRA8 => GL_BGRA,
+            PixelFormat::RGB8
+            | PixelFormat::R5G6B5
+            | PixelFormat::R11G11B10F
+            | PixelFormat::RGB9E5 => gl::RGB,
+            #[cfg(feature = "gles2")]
+            PixelFormat::L8 => gl::LUMINANCE,
+            #[cfg(not(feature = "gles2"))]
+            PixelFormat::L8 => gl::RED,
+            PixelFormat::R32F | PixelFormat::R16F | PixelFormat::R8 => gl::RED,
+            PixelFormat::RG8 => gl::RG,
+            PixelFormat::DXT1 => GL_COMPRESSED_RGBA_S3TC_DXT1_EXT,
+            PixelFormat::DXT3 => GL_COMPRESSED_RGBA_S3TC_DXT3_EXT,
+            PixelFormat::DXT5 => GL_COMPRESSED_RGBA_S3TC_DXT5_EXT,
+            PixelFormat::Depth => gl::DEPTH_COMPONENT,
+            PixelFormat::DepthStencil => gl::DEPTH_STENCIL,
+            PixelFormat::PVRTC2_RGB => GL_COMPRESSED_RGB_PVRTC_2BPPV1_IMG,
+            PixelFormat::PVRTC4_RGB => GL_COMPRESSED_RGB_PVRTC_4BPPV1_IMG,
+            PixelFormat::PVRTC2_RGBA => GL_COMPRESSED_RGBA_PVRTC_2BPPV1_IMG,
+            PixelFormat::PVRTC4_RGBA => GL_COMPRESSED_RGBA_PVRTC_4BPPV1_IMG,
+            PixelFormat::ETC2_RGB8 => GL_COMPRESSED_RGB8_ETC2,
+            PixelFormat::ETC2_SRGB8 => GL_COMPRESSED_SRGB8_ETC2,
+        }
+    }
+
+    /// Convert this pixel format to the OpenGL equivalent internal
+    /// texture format, as used by `glTexImage2D`.
+    ///
+    /// GLES2 has no sized internal formats, so when `gles2` is true
+    /// this is the same as [`gl_teximage_format()`].
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    ///
+    /// [`gl_teximage_format()`]: #method.gl_teximage_format
+    pub fn gl_teximage_internal_format(self, gles2: bool) -> gl::GLenum {
+        if gles2 {
+            return self.gl_teximage_format();
+        }
+        match self {
+            PixelFormat::RGBA8 | PixelFormat::BGRA8 => gl::RGBA8,
+            PixelFormat::RGB8 => gl::RGB8,
+            PixelFormat::RGBA4 => gl::RGBA4,
+            #[cfg(feature = "glcore33")]
+            PixelFormat::R5G6B5 => gl::RGB5,
+            #[cfg(not(feature = "glcore33"))]
+            PixelFormat::R5G6B5 => gl::RGB565,
+            PixelFormat::R5G5B5A1 => gl::RGB5_A1,
+            PixelFormat::R10G10B10A2 => gl::RGB10_A2,
+            PixelFormat::RGBA32F => gl::RGBA32F,
+            PixelFormat::RGBA16F => gl::RGBA16F,
+            PixelFormat::R11G11B10F => gl::R11F_G11F_B10F,
+            PixelFormat::RGB9E5 => gl::RGB9_E5,
+            PixelFormat::R32F => gl::R32F,
+            PixelFormat::R16F => gl::R16F,
+            PixelFormat::L8 | PixelFormat::R8 => gl::R8,
+            PixelFormat::RG8 => gl::RG8,
+            PixelFormat::Depth => gl::DEPTH_COMPONENT16,
+            PixelFormat::DepthStencil => gl::DEPTH24_STENCIL8,
+            _ => self.gl_teximage_format(),
+        }
+    }
+
+    /// Convert this depth pixel format to the OpenGL equivalent
+    /// renderbuffer format, as used by `glRenderbufferStorage`.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_depth_attachment_format(self) -> gl::GLenum {
+        match self {
+            PixelFormat::Depth => gl::DEPTH_COMPONENT16,
+            #[cfg(feature = "gles2")]
+            PixelFormat::DepthStencil => gl::DEPTH_STENCIL,
+            #[cfg(not(feature = "gles2"))]
+            PixelFormat::DepthStencil => gl::DEPTH24_STENCIL8,
+            _ => 0,
+        }
+    }
+
+    /// Convert this pixel format to the OpenGL equivalent pixel data
+    /// type, as used by `glTexImage2D` and `glReadPixels`.
+    ///
+    /// Compressed formats have no pixel data type and return `0`.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_teximage_type(self) -> gl::GLenum {
+        match self {
+            PixelFormat::RGBA32F | PixelFormat::R32F => gl::FLOAT,
+            PixelFormat::RGBA16F | PixelFormat::R16F => gl::HALF_FLOAT,
+            PixelFormat::RGBA8
+            | PixelFormat::BGRA8
+            | PixelFormat::RGB8
+            | PixelFormat::L8
+            | PixelFormat::R8
+            | PixelFormat::RG8 => gl::UNSIGNED_BYTE,
+            PixelFormat::R10G10B10A2 => gl::UNSIGNED_INT_2_10_10_10_REV,
+            PixelFormat::R11G11B10F => gl::UNSIGNED_INT_10F_11F_11F_REV,
+            PixelFormat::RGB9E5 => gl::UNSIGNED_INT_5_9_9_9_REV,
+            PixelFormat::R5G5B5A1 => gl::UNSIGNED_SHORT_5_5_5_1,
+            PixelFormat::R5G6B5 => gl::UNSIGNED_SHORT_5_6_5,
+            PixelFormat::RGBA4 => gl::UNSIGNED_SHORT_4_4_4_4,
+            PixelFormat::Depth => gl::UNSIGNED_SHORT,
+            PixelFormat::DepthStencil => gl::UNSIGNED_INT_24_8,
+            _ => 0,
+        }
+    }
+}
+
+impl PrimitiveType {
+    /// Convert this primitive type to the OpenGL equivalent.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_primitive_type(self) -> gl::GLenum {
+        match self {
+            PrimitiveType::Points => gl::POINTS,
+            PrimitiveType::Lines => gl::LINES,
+            PrimitiveType::LineStrip => gl::LINE_STRIP,
+            PrimitiveType::Triangles => gl::TRIANGLES,
+            PrimitiveType::TriangleStrip => gl::TRIANGLE_STRIP,
+        }
+    }
+}
+
 impl ShaderStage {
     /// Convert this shader stage to the OpenGL equivalent.
     ///
@@ -49,6 +320,109 @@ impl ShaderStage {
     }
 }
 
+impl VertexFormat {
+    /// The number of components of this vertex format, as used by
+    /// `glVertexAttribPointer`.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_vertex_format_size(self) -> gl::GLint {
+        match self {
+            VertexFormat::Float => 1,
+            VertexFormat::Float2 | VertexFormat::Short2 | VertexFormat::Short2N => 2,
+            VertexFormat::Float3 => 3,
+            VertexFormat::Float4
+            | VertexFormat::Byte4
+            | VertexFormat::Byte4N
+            | VertexFormat::UByte4
+            | VertexFormat::UByte4N
+            | VertexFormat::Short4
+            | VertexFormat::Short4N
+            | VertexFormat::UInt10N2 => 4,
+        }
+    }
+
+    /// Convert this vertex format to the OpenGL equivalent component type.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_vertex_format_type(self) -> gl::GLenum {
+        match self {
+            VertexFormat::Float
+            | VertexFormat::Float2
+            | VertexFormat::Float3
+            | VertexFormat::Float4 => gl::FLOAT,
+            VertexFormat::Byte4 | VertexFormat::Byte4N => gl::BYTE,
+            VertexFormat::UByte4 | VertexFormat::UByte4N => gl::UNSIGNED_BYTE,
+            VertexFormat::Short2
+            | VertexFormat::Short2N
+            | VertexFormat::Short4
+            | VertexFormat::Short4N => gl::SHORT,
+            VertexFormat::UInt10N2 => gl::UNSIGNED_INT_2_10_10_10_REV,
+        }
+    }
+
+    /// Whether this vertex format is normalized to the `0.0..1.0` or
+    /// `-1.0..1.0` range when read by a shader.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_vertex_format_normalized(self) -> bool {
+        matches!(
+            self,
+            VertexFormat::Byte4N
+                | VertexFormat::UByte4N
+                | VertexFormat::Short2N
+                | VertexFormat::Short4N
+                | VertexFormat::UInt10N2
+        )
+    }
+}
+
+impl Wrap {
+    /// Convert this wrap mode to the OpenGL equivalent.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_wrap(self) -> gl::GLenum {
+        match self {
+            Wrap::Repeat => gl::REPEAT,
+            Wrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+            Wrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+        }
+    }
+}
+
+impl TextureSwizzle {
+    /// Convert this texture swizzle to the OpenGL equivalent.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_swizzle(self) -> gl::GLenum {
+        match self {
+            TextureSwizzle::Red => gl::RED,
+            TextureSwizzle::Green => gl::GREEN,
+            TextureSwizzle::Blue => gl::BLUE,
+            TextureSwizzle::Alpha => gl::ALPHA,
+            TextureSwizzle::Zero => gl::ZERO,
+            TextureSwizzle::One => gl::ONE,
+        }
+    }
+}
+
+impl StencilOp {
+    /// Convert this stencil operation to the OpenGL equivalent.
+    ///
+    /// This is only present when the `gl` feature is enabled.
+    pub fn gl_stencil_op(self) -> gl::GLenum {
+        match self {
+            StencilOp::Keep => gl::KEEP,
+            StencilOp::Zero => gl::ZERO,
+            StencilOp::Replace => gl::REPLACE,
+            StencilOp::IncrClamp => gl::INCR,
+            StencilOp::DecrClamp => gl::DECR,
+            StencilOp::Invert => gl::INVERT,
+            StencilOp::IncrWrap => gl::INCR_WRAP,
+            StencilOp::DecrWrap => gl::DECR_WRAP,
+        }
+    }
+}
+
 impl Usage {
     /// Convert this usage flag to the OpenGL equivalent.
     ///
@@ -61,3 +435,61 @@ impl Usage {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_funcs_translate_to_gl() {
+        let table = [
+            (CompareFunc::Never, gl::NEVER),
+            (CompareFunc::Less, gl::LESS),
+            (CompareFunc::Equal, gl::EQUAL),
+            (CompareFunc::LessEqual, gl::LEQUAL),
+            (CompareFunc::Greater, gl::GREATER),
+            (CompareFunc::NotEqual, gl::NOTEQUAL),
+            (CompareFunc::GreaterEqual, gl::GEQUAL),
+            (CompareFunc::Always, gl::ALWAYS),
+        ];
+        for &(func, gl_func) in &table {
+            assert_eq!(func.gl_compare_func(), gl_func, "{:?}", func);
+        }
+    }
+
+    #[test]
+    fn stencil_ops_translate_to_gl() {
+        let table = [
+            (StencilOp::Keep, gl::KEEP),
+            (StencilOp::Zero, gl::ZERO),
+            (StencilOp::Replace, gl::REPLACE),
+            (StencilOp::IncrClamp, gl::INCR),
+            (StencilOp::DecrClamp, gl::DECR),
+            (StencilOp::Invert, gl::INVERT),
+            (StencilOp::IncrWrap, gl::INCR_WRAP),
+            (StencilOp::DecrWrap, gl::DECR_WRAP),
+        ];
+        for &(op, gl_op) in &table {
+            assert_eq!(op.gl_stencil_op(), gl_op, "{:?}", op);
+        }
+    }
+
+    #[test]
+    fn luminance_swizzle_translates_to_gl() {
+        let rrr1 = [
+            TextureSwizzle::Red,
+            TextureSwizzle::Red,
+            TextureSwizzle::Red,
+            TextureSwizzle::One,
+        ];
+        let gl_swizzle: Vec<gl::GLenum> = rrr1.iter().map(|s| s.gl_swizzle()).collect();
+        assert_eq!(gl_swizzle, [gl::RED, gl::RED, gl::RED, gl::ONE]);
+
+        let identity: Vec<gl::GLenum> = TextureSwizzle::IDENTITY
+            .iter()
+            .map(|s| s.gl_swizzle())
+            .collect();
+        assert_eq!(identity, [gl::RED, gl::GREEN, gl::BLUE, gl::ALPHA]);
+        assert_eq!(TextureSwizzle::Zero.gl_swizzle(), gl::ZERO);
+    }
+}
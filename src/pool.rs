@@ -12,55 +12,404 @@ pub const SLOT_MASK: u32 = (1 << SLOT_SHIFT) - 1;
 pub const MAX_POOL_SIZE: usize = 1 << SLOT_SHIFT;
 pub const DEFAULT_PASS_POOL_SIZE: u32 = 16;
 
+/// The reason why a pool could not be created.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PoolError {
+    /// The pool size is 0 or not below `MAX_POOL_SIZE`.
+    InvalidSize(usize),
+}
+
 pub struct Pool<R: ResourceHandle + Sized> {
     resources: Vec<Option<R::Resource>>,
+    /// The ID of the handle currently occupying each slot, or 0 if the slot
+    /// is free.
+    ids: Vec<u32>,
     free_queue: VecDeque<u32>,
     unique_counter: u32,
+    /// Reuse the most recently freed slot first.
+    lifo: bool,
+    /// The resource type named when panicking on lookups of invalid handles,
+    /// or `None` if such lookups just fail.
+    panic_on_invalid: Option<&'static str>,
 }
 
 impl<R: ResourceHandle + Sized> Pool<R> {
-    pub fn new(num: usize) -> Self {
-        assert!(num < MAX_POOL_SIZE);
-        assert!(num > 0);
+    pub fn new(num: usize) -> Result<Self, PoolError> {
+        if num == 0 || num >= MAX_POOL_SIZE {
+            return Err(PoolError::InvalidSize(num));
+        }
 
         // 0 is an reserved for 'invalid id', so bump size with one.
         let mut resources = Vec::<Option<R::Resource>>::with_capacity(num + 1);
-        let mut free_queue = VecDeque::with_capacity(num + 1);
-        for i in 1..num + 2 {
+        let mut free_queue = VecDeque::with_capacity(num);
+        resources.push(None);
+        for i in 1..num + 1 {
             resources.push(None);
             free_queue.push_back(i as u32);
         }
-        Pool {
+        Ok(Pool {
             resources: resources,
+            ids: vec![0; num + 1],
             free_queue: free_queue,
             unique_counter: 0,
+            lifo: false,
+            panic_on_invalid: None,
+        })
+    }
+
+    /// Create a pool that reuses freed slots immediately, rather than after
+    /// all other free slots.
+    ///
+    /// Together with the generation counter in handle IDs, this makes
+    /// accesses through dangling handles fail deterministically, which
+    /// helps to find use-after-free bugs.
+    pub fn new_lifo(num: usize) -> Result<Self, PoolError> {
+        let mut pool = Pool::new(num)?;
+        pool.lifo = true;
+        Ok(pool)
+    }
+
+    /// Panic when looking up a handle with `lookup_checked()` that doesn't
+    /// refer to an allocated slot, naming the resource type `name` in the
+    /// message.
+    pub fn panic_on_invalid(mut self, name: &'static str) -> Self {
+        self.panic_on_invalid = Some(name);
+        self
+    }
+
+    fn free_slot(&mut self, index: usize) {
+        self.ids[index] = 0;
+        if self.lifo {
+            self.free_queue.push_front(index as u32);
+        } else {
+            self.free_queue.push_back(index as u32);
         }
     }
 
+    /// Allocate a slot, returning a handle whose ID combines the slot index
+    /// with a generation counter, so that stale handles to a reused slot
+    /// can be told apart from the current one.
     pub fn alloc(&mut self) -> Option<R> {
-        self.free_queue.pop_front().map(R::with)
+        let index = self.free_queue.pop_front()?;
+        self.unique_counter = (self.unique_counter % (u32::MAX >> SLOT_SHIFT)) + 1;
+        let id = (self.unique_counter << SLOT_SHIFT) | (index & SLOT_MASK);
+        self.ids[index as usize] = id;
+        Some(R::with(id))
+    }
+
+    /// The slot index of a handle, if the handle is live in this pool.
+    fn index(&self, handle: &R) -> Option<usize> {
+        let index = (handle.id() & SLOT_MASK) as usize;
+        match self.ids.get(index) {
+            Some(&id) if id != 0 && id == handle.id() => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Store the resource for an allocated handle.
+    pub fn insert(&mut self, handle: R, resource: R::Resource) {
+        if let Some(index) = self.index(&handle) {
+            self.resources[index] = Some(resource);
+        }
     }
 
-    pub fn discard(&mut self, handle: R, backend: &mut ::backend::Backend) {
-        // Make sure that this isn't a double free.
-        debug_assert_eq!(self.free_queue.contains(&handle.id()), false);
-        if let Some(ref mut r) = self.resources[handle.id() as usize] {
-            // backend.discard(r);
-            self.free_queue.push_back(handle.id());
+    /// Return an allocated handle whose initialization failed to the pool.
+    pub fn release(&mut self, handle: R) {
+        self.discard(handle);
+    }
+
+    /// Free the slot of a handle, returning its resource.
+    ///
+    /// Discarding a stale or already discarded handle is a no-op.
+    pub fn discard(&mut self, handle: R) -> Option<R::Resource> {
+        let index = self.index(&handle)?;
+        self.free_slot(index);
+        self.resources[index].take()
+    }
+
+    /// Free all slots, returning the resources that were still alive.
+    pub fn clear(&mut self) -> Vec<R::Resource> {
+        let mut live = Vec::new();
+        for index in 1..self.ids.len() {
+            if self.ids[index] != 0 {
+                self.free_slot(index);
+                live.extend(self.resources[index].take());
+            }
         }
+        live
+    }
+
+    /// Whether the handle refers to a slot that is currently allocated to it.
+    ///
+    /// This is `false` for handles whose resource was destroyed, even if the
+    /// slot has since been reused for another resource.
+    pub fn contains(&self, handle: &R) -> bool {
+        self.index(handle).is_some()
+    }
+
+    /// The state of the resource the handle refers to, reading the state of
+    /// initialized resources with `resource_state`.
+    pub fn state<F>(&self, handle: &R, resource_state: F) -> ResourceState
+    where
+        F: FnOnce(&R::Resource) -> ResourceState,
+    {
+        match self.index(handle) {
+            Some(index) => self.resources[index]
+                .as_ref()
+                .map_or(ResourceState::Alloc, resource_state),
+            None => ResourceState::Initial,
+        }
+    }
+
+    /// Iterate over the resources that are stored in the pool.
+    pub fn resources_mut(&mut self) -> impl Iterator<Item = &mut R::Resource> {
+        self.resources.iter_mut().filter_map(|res| res.as_mut())
+    }
+
+    /// The slot index of a handle that is looked up, panicking if the handle
+    /// is invalid and the pool was configured to do so.
+    ///
+    /// The default handle marks an unused binding rather than a stale one,
+    /// so it never panics.
+    fn checked_index(&self, handle: &R) -> Option<usize> {
+        let index = self.index(handle);
+        if handle.id() == 0 {
+            return index;
+        }
+        if let (None, Some(name)) = (index, self.panic_on_invalid) {
+            let slot = (handle.id() & SLOT_MASK) as usize;
+            let generation = handle.id() >> SLOT_SHIFT;
+            match self.ids.get(slot) {
+                Some(&0) => panic!(
+                    "invalid {} handle: slot {} (generation {}) is not allocated",
+                    name, slot, generation
+                ),
+                Some(&id) => panic!(
+                    "invalid {} handle: slot {} holds generation {}, not {}",
+                    name,
+                    slot,
+                    id >> SLOT_SHIFT,
+                    generation
+                ),
+                None => panic!("invalid {} handle: slot {} is out of range", name, slot),
+            }
+        }
+        index
     }
 
     pub fn lookup(&self, handle: &R) -> Option<&R::Resource> {
-        self.resources[handle.id() as usize].as_ref()
+        self.index(handle)
+            .and_then(|index| self.resources[index].as_ref())
     }
 
     pub fn lookup_mut(&mut self, handle: &R) -> Option<&mut R::Resource> {
-        self.resources[handle.id() as usize].as_mut()
+        match self.index(handle) {
+            Some(index) => self.resources[index].as_mut(),
+            None => None,
+        }
+    }
+
+    /// Look up a handle that was passed in by the application, panicking
+    /// if it is invalid and the pool was configured to do so.
+    pub fn lookup_checked(&self, handle: &R) -> Option<&R::Resource> {
+        self.checked_index(handle)
+            .and_then(|index| self.resources[index].as_ref())
+    }
+
+    /// Mutable version of `lookup_checked()`.
+    pub fn lookup_checked_mut(&mut self, handle: &R) -> Option<&mut R::Resource> {
+        match self.checked_index(handle) {
+            Some(index) => self.resources[index].as_mut(),
+            None => None,
+        }
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Slot {
     pub id: u32,
     pub state: ResourceState,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Context;
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    struct Handle(u32);
+
+    impl ResourceHandle for Handle {
+        type Resource = &'static str;
+        type Description = ();
+
+        fn with(id: u32) -> Self {
+            Handle(id)
+        }
+
+        fn alloc(_ctx: &mut Context) -> Option<Self> {
+            unreachable!()
+        }
+
+        fn initialize(&self, _ctx: &mut Context, _desc: ()) -> Option<Self> {
+            unreachable!()
+        }
+
+        fn discard(self, _ctx: &mut Context) {
+            unreachable!()
+        }
+
+        fn id(&self) -> u32 {
+            self.0
+        }
+
+        fn state(&self, _ctx: &Context) -> ResourceState {
+            unreachable!()
+        }
+    }
+
+    fn slot(handle: Handle) -> u32 {
+        handle.0 & SLOT_MASK
+    }
+
+    fn generation(handle: Handle) -> u32 {
+        handle.0 >> SLOT_SHIFT
+    }
+
+    #[test]
+    fn new_rejects_invalid_sizes() {
+        assert_eq!(
+            Pool::<Handle>::new(0).err(),
+            Some(PoolError::InvalidSize(0))
+        );
+        assert_eq!(
+            Pool::<Handle>::new(MAX_POOL_SIZE).err(),
+            Some(PoolError::InvalidSize(MAX_POOL_SIZE))
+        );
+        assert!(Pool::<Handle>::new(MAX_POOL_SIZE - 1).is_ok());
+    }
+
+    #[test]
+    fn alloc_fails_when_the_pool_is_full() {
+        let mut pool = Pool::<Handle>::new(2).unwrap();
+        let a = pool.alloc().unwrap();
+        let b = pool.alloc().unwrap();
+        assert_ne!(slot(a), 0);
+        assert_ne!(slot(a), slot(b));
+        assert!(pool.alloc().is_none());
+        pool.discard(a);
+        assert!(pool.alloc().is_some());
+    }
+
+    #[test]
+    fn fifo_pool_reuses_the_oldest_free_slot() {
+        let mut pool = Pool::<Handle>::new(4).unwrap();
+        let a = pool.alloc().unwrap();
+        let b = pool.alloc().unwrap();
+        pool.discard(a);
+        pool.discard(b);
+        let c = pool.alloc().unwrap();
+        assert_ne!(slot(c), slot(a));
+        assert_ne!(slot(c), slot(b));
+    }
+
+    #[test]
+    fn lifo_pool_reuses_the_most_recently_freed_slot() {
+        let mut pool = Pool::<Handle>::new_lifo(4).unwrap();
+        let a = pool.alloc().unwrap();
+        let b = pool.alloc().unwrap();
+        pool.discard(a);
+        pool.discard(b);
+        assert_eq!(slot(pool.alloc().unwrap()), slot(b));
+        assert_eq!(slot(pool.alloc().unwrap()), slot(a));
+    }
+
+    #[test]
+    fn generation_wraps_around_without_reaching_zero() {
+        let mut pool = Pool::<Handle>::new(4).unwrap();
+        let max_generation = u32::MAX >> SLOT_SHIFT;
+        pool.unique_counter = max_generation - 1;
+        let a = pool.alloc().unwrap();
+        assert_eq!(generation(a), max_generation);
+        let b = pool.alloc().unwrap();
+        assert_eq!(generation(b), 1);
+        assert_ne!(a.0, 0);
+        assert_ne!(b.0, 0);
+    }
+
+    #[test]
+    fn stale_handles_to_a_reused_slot_are_detected() {
+        let mut pool = Pool::<Handle>::new_lifo(4).unwrap();
+        let old = pool.alloc().unwrap();
+        pool.insert(old, "old");
+        assert_eq!(pool.discard(old), Some("old"));
+        let new = pool.alloc().unwrap();
+        pool.insert(new, "new");
+        assert_eq!(slot(new), slot(old));
+        assert_ne!(new, old);
+
+        assert!(!pool.contains(&old));
+        assert_eq!(pool.lookup(&old), None);
+        assert_eq!(pool.lookup_mut(&old), None);
+        assert_eq!(
+            pool.state(&old, |_| ResourceState::Valid),
+            ResourceState::Initial
+        );
+        // Discarding the stale handle again leaves the new resource alone.
+        assert_eq!(pool.discard(old), None);
+        assert_eq!(pool.lookup(&new), Some(&"new"));
+    }
+
+    #[test]
+    fn state_tracks_allocation_and_insertion() {
+        let mut pool = Pool::<Handle>::new(4).unwrap();
+        let a = pool.alloc().unwrap();
+        assert_eq!(
+            pool.state(&a, |_| ResourceState::Valid),
+            ResourceState::Alloc
+        );
+        pool.insert(a, "a");
+        assert_eq!(
+            pool.state(&a, |_| ResourceState::Valid),
+            ResourceState::Valid
+        );
+        pool.release(a);
+        assert_eq!(
+            pool.state(&a, |_| ResourceState::Valid),
+            ResourceState::Initial
+        );
+    }
+
+    #[test]
+    fn clear_returns_the_live_resources() {
+        let mut pool = Pool::<Handle>::new(4).unwrap();
+        let a = pool.alloc().unwrap();
+        let b = pool.alloc().unwrap();
+        let c = pool.alloc().unwrap();
+        pool.insert(a, "a");
+        pool.insert(c, "c");
+        pool.discard(a);
+        assert_eq!(pool.clear(), vec!["c"]);
+        assert!(!pool.contains(&b));
+        assert!(!pool.contains(&c));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid test handle")]
+    fn checked_lookup_of_a_stale_handle_panics_in_panic_mode() {
+        let mut pool = Pool::<Handle>::new(4).unwrap().panic_on_invalid("test");
+        let a = pool.alloc().unwrap();
+        pool.discard(a);
+        pool.lookup_checked(&a);
+    }
+
+    #[test]
+    fn unchecked_and_default_lookups_never_panic() {
+        let mut pool = Pool::<Handle>::new(4).unwrap().panic_on_invalid("test");
+        let a = pool.alloc().unwrap();
+        pool.discard(a);
+        assert_eq!(pool.lookup(&a), None);
+        assert_eq!(pool.lookup_checked(&Handle(0)), None);
+        assert_eq!(pool.lookup_checked_mut(&Handle(0)), None);
+    }
+}